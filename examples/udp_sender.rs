@@ -0,0 +1,29 @@
+//loopback udp sender for trying out the udp source without a ground modem
+//sends the same spinning quaternion as SendSerialData.ino, one json record per datagram
+//run the viewer, pick the UDP Listener source, then run:
+//cargo run --example udp_sender -- 127.0.0.1:5005
+
+use std::net::UdpSocket;
+use std::thread;
+use std::time::{Duration, Instant};
+
+fn main() -> std::io::Result<()> {
+    let target = std::env::args().nth(1).unwrap_or_else(|| "127.0.0.1:5005".to_string());
+    let socket = UdpSocket::bind("127.0.0.1:0")?;
+    let start = Instant::now();
+    println!("Sending telemetry to {}", target);
+    let mut i = 1.0_f32;
+    loop {
+        //rotate about the y axis one degree per packet, same as the arduino sketch
+        let half_angle = i.to_radians() / 2.0;
+        let record = format!(
+            "{{\"x\":0.0,\"y\":{},\"z\":0.0,\"w\":{},\"time\":{}}}",
+            half_angle.sin(),
+            half_angle.cos(),
+            start.elapsed().as_millis() as u32,
+        );
+        socket.send_to(record.as_bytes(), &target)?;
+        i += 1.0;
+        thread::sleep(Duration::from_millis(10));
+    }
+}
@@ -0,0 +1,272 @@
+//calibration of the imu in the airframe, applied to every sample after the frame mapping
+//the mounting offset is how the imu sits in the rocket, it is taken back out so the model shows the rocket and not the imu
+//the tare is an orientation that is shown as upright, usually captured with the rocket sitting on the pad
+//
+//both are saved with the frame mapping and the rocket model in a rocket profile, one json file per rocket in the profiles folder
+
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use bevy::prelude::*;
+use bevy_egui::{EguiContexts, egui};
+use serde::{Deserialize, Serialize};
+use crate::{ArduinoData, CurrentData};
+use crate::frame::{FrameMapping, PreparedMapping};
+use crate::history::TelemetryHistory;
+use crate::model::RocketModel;
+
+const PROFILE_DIRECTORY: &str = "profiles"; //folder rocket profiles are saved in, relative to the working directory
+const DEFAULT_PROFILE_NAME: &str = "rocket";
+
+//resource that holds the mounting offset and tare
+#[derive(Resource, Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct Calibration {
+    pub mounting_offset: [f32; 3], //rotation of the imu in the airframe, xyz euler angles in degrees
+    pub tare: Option<[f32; 4]>, //orientation shown as upright, xyzw, none to show the orientation as is
+}
+
+impl Calibration {
+    //the mounting offset as a rotation
+    fn mounting_rotation(&self) -> Quat {
+        let [x, y, z] = self.mounting_offset.map(f32::to_radians);
+        Quat::from_euler(EulerRot::XYZ, x, y, z)
+    }
+
+    //the rocket's orientation from the imu's orientation in the world frame, with the mounting offset taken out
+    fn body(&self, sensor: Quat) -> Quat {
+        (sensor * self.mounting_rotation().inverse()).normalize()
+    }
+
+    //the calibration with its rotations worked out, for calibrating many samples
+    pub fn prepare(&self) -> PreparedCalibration {
+        PreparedCalibration {
+            mounting_inverse: self.mounting_rotation().inverse(),
+            tare_inverse: self.tare.map(|tare| Quat::from_array(tare).inverse()),
+        }
+    }
+
+    //makes the rocket's current orientation the one shown as upright
+    fn capture_tare(&mut self, sensor: Quat) {
+        self.tare = Some(self.body(sensor).to_array());
+    }
+
+    //takes the imu's current orientation as the mounting offset, for when the rocket is standing exactly upright
+    //the tare is cleared since the rocket is upright already
+    fn capture_mounting_offset(&mut self, sensor: Quat) {
+        let (x, y, z) = sensor.normalize().to_euler(EulerRot::XYZ);
+        self.mounting_offset = [x, y, z].map(f32::to_degrees);
+        self.tare = None;
+    }
+}
+
+//a calibration with the mounting rotation and the tare inverted once, so a whole history can be calibrated without redoing the trig per sample
+#[derive(Debug, Clone, Copy)]
+pub struct PreparedCalibration {
+    mounting_inverse: Quat,
+    tare_inverse: Option<Quat>, //none to show the orientation as is
+}
+
+impl PreparedCalibration {
+    //the orientation to show for the imu's orientation in the world frame, with the mounting offset and the tare taken out
+    pub fn apply(&self, sensor: Quat) -> Quat {
+        let body = (sensor * self.mounting_inverse).normalize();
+        match self.tare_inverse {
+            Some(tare_inverse) => (tare_inverse * body).normalize(),
+            None => body,
+        }
+    }
+}
+
+//the frame mapping and the calibration prepared together, for putting a whole history or recording into the world frame
+#[derive(Debug, Clone, Copy)]
+pub struct WorldTransform {
+    mapping: PreparedMapping,
+    calibration: PreparedCalibration,
+}
+
+impl WorldTransform {
+    //works out the mapping and the calibration once
+    pub fn new(frame_mapping: &FrameMapping, calibration: &Calibration) -> Self {
+        Self {
+            mapping: frame_mapping.prepare(),
+            calibration: calibration.prepare(),
+        }
+    }
+
+    //the orientation to show for a sample, put through the frame mapping and then the calibration
+    pub fn apply(&self, data: &ArduinoData) -> Quat {
+        self.calibration.apply(self.mapping.apply(data))
+    }
+}
+
+//what a rocket profile file holds
+#[derive(Serialize, Deserialize, Debug)]
+struct RocketProfile {
+    frame_mapping: FrameMapping,
+    calibration: Calibration,
+    #[serde(default)]
+    model_path: Option<PathBuf>, //none in profiles saved before the model could be picked, the model is left as it is
+}
+
+//resource that holds the name of the profile being edited and the outcome of the last save or load for the ui
+#[derive(Resource)]
+pub struct ProfileEditor {
+    name: String,
+    saved_profiles: Vec<String>, //names of the profiles in the profile folder, refreshed after saving
+    last_result: Option<Result<String, String>>,
+}
+
+impl Default for ProfileEditor {
+    fn default() -> Self {
+        Self {
+            name: DEFAULT_PROFILE_NAME.to_string(),
+            saved_profiles: list_profiles(),
+            last_result: None,
+        }
+    }
+}
+
+//file a profile with the given name is saved to
+fn profile_path(name: &str) -> PathBuf {
+    PathBuf::from(PROFILE_DIRECTORY).join(format!("{}.json", name))
+}
+
+//names of every profile in the profile folder, empty if the folder does not exist yet
+fn list_profiles() -> Vec<String> {
+    let Ok(entries) = fs::read_dir(PROFILE_DIRECTORY) else {
+        return vec![];
+    };
+    let mut names: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "json"))
+        .filter_map(|path| path.file_stem().map(|stem| stem.to_string_lossy().into_owned()))
+        .collect();
+    names.sort();
+    names
+}
+
+//writes the frame mapping, calibration, and rocket model to the named profile, replacing it if it exists
+fn save_profile(name: &str, frame_mapping: &FrameMapping, calibration: &Calibration, rocket_model: &RocketModel) -> io::Result<PathBuf> {
+    if name.is_empty() || name.contains(['/', '\\']) {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "Profile names can not be empty or have slashes in them"));
+    }
+    let path = profile_path(name);
+    let profile = RocketProfile {
+        frame_mapping: frame_mapping.clone(),
+        calibration: calibration.clone(),
+        model_path: Some(rocket_model.path.clone()),
+    };
+    let json = serde_json::to_string_pretty(&profile).map_err(io::Error::other)?;
+    fs::create_dir_all(PROFILE_DIRECTORY)
+        .and_then(|()| fs::write(&path, json))
+        .map_err(|e| io::Error::new(e.kind(), format!("Could not save {}: {}", path.display(), e)))?;
+    Ok(path)
+}
+
+//reads the named profile
+fn load_profile(name: &str) -> io::Result<RocketProfile> {
+    let path = profile_path(name);
+    let json = fs::read_to_string(&path)
+        .map_err(|e| io::Error::new(e.kind(), format!("Could not read {}: {}", path.display(), e)))?;
+    serde_json::from_str(&json)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{} is not a rocket profile: {}", path.display(), e)))
+}
+
+
+// UI SYSTEMS
+
+//rocket profile ui system, runs every frame
+//tare and mounting offset controls, and saving and loading them with the frame mapping and rocket model as a rocket profile
+pub fn ui_system_calibration(
+    mut contexts: EguiContexts,
+    mut calibration: ResMut<Calibration>,
+    mut frame_mapping: ResMut<FrameMapping>,
+    mut editor: ResMut<ProfileEditor>,
+    mut rocket_model: ResMut<RocketModel>,
+    history: Res<TelemetryHistory>,
+    current_data: Option<Res<CurrentData>>,
+) -> Result<(), BevyError> {
+    let ctx = contexts.ctx_mut()?;
+    //edit a copy so the history only gets remapped when something was actually changed
+    let mut edited = calibration.clone();
+    //the sample on screen in the imu's world frame orientation, what tare and capture work from
+    let sensor = current_data
+        .and_then(|current_data| history.at_or_before(current_data.timeline_ms))
+        .map(|sample| frame_mapping.apply(&sample.data));
+    egui::Window::new("Rocket Profile")
+        .default_width(250.0)
+        .default_open(false)
+        .show(ctx, |ui| {
+            let editor = &mut *editor;
+            ui.horizontal(|ui| {
+                ui.label("Profile:");
+                ui.text_edit_singleline(&mut editor.name);
+                egui::ComboBox::from_id_salt("saved_profiles")
+                    .selected_text("Saved")
+                    .show_ui(ui, |ui| {
+                        for name in &editor.saved_profiles {
+                            if ui.selectable_label(*name == editor.name, name).clicked() {
+                                editor.name = name.clone();
+                            }
+                        }
+                    });
+            });
+            ui.horizontal(|ui| {
+                if ui.button("Save").clicked() {
+                    editor.last_result = Some(save_profile(&editor.name, &frame_mapping, &edited, &rocket_model)
+                        .map(|path| format!("Saved {}", path.display()))
+                        .map_err(|e| e.to_string()));
+                    editor.saved_profiles = list_profiles();
+                }
+                if ui.button("Load").clicked() {
+                    editor.last_result = Some(match load_profile(&editor.name) {
+                        Ok(profile) => {
+                            frame_mapping.set_if_neq(profile.frame_mapping);
+                            if let Some(path) = profile.model_path {
+                                rocket_model.set_if_neq(RocketModel { path });
+                            }
+                            edited = profile.calibration;
+                            Ok(format!("Loaded {}", editor.name))
+                        }
+                        Err(e) => Err(e.to_string()),
+                    });
+                }
+            });
+            match &editor.last_result {
+                Some(Ok(message)) => {
+                    ui.label(message);
+                }
+                Some(Err(message)) => {
+                    ui.colored_label(egui::Color32::LIGHT_RED, message);
+                }
+                None => (),
+            }
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.label("Mounting Offset:");
+                for (axis, angle) in ["x ", "y ", "z "].iter().zip(edited.mounting_offset.iter_mut()) {
+                    ui.add(egui::DragValue::new(angle).range(-180.0..=180.0).speed(0.5).prefix(*axis).suffix("°"));
+                }
+            });
+            ui.horizontal(|ui| {
+                if ui.add_enabled(sensor.is_some(), egui::Button::new("Capture Mounting Offset")).clicked() && let Some(sensor) = sensor {
+                    edited.capture_mounting_offset(sensor);
+                }
+                if ui.button("Reset Mounting Offset").clicked() {
+                    edited.mounting_offset = [0.0; 3];
+                }
+            });
+            ui.horizontal(|ui| {
+                if ui.add_enabled(sensor.is_some(), egui::Button::new("Tare")).clicked() && let Some(sensor) = sensor {
+                    edited.capture_tare(sensor);
+                }
+                if ui.add_enabled(edited.tare.is_some(), egui::Button::new("Clear Tare")).clicked() {
+                    edited.tare = None;
+                }
+            });
+            ui.label(if edited.tare.is_some() { "Tared" } else { "Not tared" });
+        });
+    calibration.set_if_neq(edited);
+    Ok(())
+}
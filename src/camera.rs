@@ -0,0 +1,404 @@
+//orbit camera for the 3d view, so the rocket can be looked at from any side
+//left drag or the arrow keys orbit around the focus point, right or middle drag or wasd pan, the scroll wheel or page up and down zoom
+//the camera keeps moving for a moment after the mouse is let go and slows down smoothly
+//input is ignored while the pointer is over an egui window or a text field has the keyboard
+//
+//the camera can orbit the world, orbit the rocket's body frame so it turns with the rocket, or stand still on the ground watching the rocket
+//views can be saved as named bookmarks, kept in a json file in the working directory
+
+use std::f32::consts::FRAC_PI_2;
+use std::fs;
+use std::io;
+use bevy::input::mouse::{AccumulatedMouseMotion, AccumulatedMouseScroll, MouseScrollUnit};
+use bevy::prelude::*;
+use bevy_egui::{EguiContexts, egui};
+use serde::{Deserialize, Serialize};
+use crate::Rocket;
+
+const ORBIT_SENSITIVITY: f32 = 0.005; //radians per pixel dragged
+const PAN_SENSITIVITY: f32 = 0.002; //distances per pixel dragged, scaled by the distance so panning feels the same at any zoom
+const ZOOM_SENSITIVITY: f32 = 3.0; //zoom speed added per scroll line
+const KEY_ORBIT_SPEED: f32 = 1.5; //radians per second while an orbit key is held
+const KEY_PAN_SPEED: f32 = 1.0; //distances per second while a pan key is held
+const KEY_ZOOM_SPEED: f32 = 2.0; //zoom speed while a zoom key is held
+const INERTIA_TIME: f32 = 0.15; //seconds for the camera to lose most of its speed once input stops
+const MIN_DISTANCE: f32 = 0.1;
+const MAX_DISTANCE: f32 = 50.0;
+const MAX_PITCH: f32 = FRAC_PI_2 - 0.01; //just short of straight up or down so the camera does not flip over
+const PIXELS_PER_SCROLL_LINE: f32 = 50.0; //touchpads scroll in pixels, mouse wheels in lines
+const DEFAULT_OBSERVER_POSITION: [f32; 3] = [2.0, 0.0, 2.0]; //a couple of rocket lengths from the pad at ground level
+const BOOKMARK_FILE: &str = "camera_bookmarks.json"; //file camera bookmarks are saved in, relative to the working directory
+
+//ways the camera can follow the rocket
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Eq, PartialEq)]
+pub enum CameraMode {
+    Fixed, //orbits a point in the world
+    Chase, //orbits a point in the rocket's body frame and turns with the rocket, so the world seems to turn instead
+    Observer, //stands still at a point relative to the pad and keeps looking at the rocket
+}
+
+impl CameraMode {
+    //every mode in the order they are shown in the dropdown
+    pub const ALL: [CameraMode; 3] = [
+        CameraMode::Fixed,
+        CameraMode::Chase,
+        CameraMode::Observer,
+    ];
+
+    //name shown in the mode dropdown
+    pub fn label(&self) -> &'static str {
+        match self {
+            CameraMode::Fixed => "Fixed World",
+            CameraMode::Chase => "Chase (Body Locked)",
+            CameraMode::Observer => "Ground Observer",
+        }
+    }
+}
+
+//resource that holds the camera mode and where the ground observer stands
+#[derive(Resource, Debug)]
+pub struct CameraSettings {
+    pub mode: CameraMode,
+    pub observer_position: [f32; 3], //relative to the pad, which is the world origin
+}
+
+impl Default for CameraSettings {
+    fn default() -> Self {
+        Self {
+            mode: CameraMode::Fixed,
+            observer_position: DEFAULT_OBSERVER_POSITION,
+        }
+    }
+}
+
+//resource that says whether egui is using the mouse or keyboard, set by the ui every frame
+#[derive(Resource, Default, Debug)]
+pub struct EguiInputCapture {
+    pub pointer: bool, //the pointer is over an egui window or dragging something in one
+    pub keyboard: bool, //an egui text field has the keyboard
+}
+
+//component for a camera that orbits around a focus point
+#[derive(Component, Debug)]
+pub struct OrbitCamera {
+    pub focus: Vec3, //point the camera orbits around and looks at
+    pub yaw: f32, //radians around the y axis, zero looks along -z
+    pub pitch: f32, //radians above the focus point
+    pub distance: f32, //distance from the focus point
+    yaw_speed: f32, //radians per second
+    pitch_speed: f32, //radians per second
+    pan_speed: Vec3, //distances per second in world space
+    zoom_speed: f32, //how fast the distance shrinks, negative to grow, per second on a log scale
+    default_pose: (Vec3, f32, f32, f32), //focus, yaw, pitch, and distance to go back to when the view is reset
+}
+
+impl OrbitCamera {
+    //orbit camera at position looking at focus
+    pub fn new(position: Vec3, focus: Vec3) -> Self {
+        let offset = position - focus;
+        let distance = offset.length().max(MIN_DISTANCE);
+        let yaw = offset.x.atan2(offset.z);
+        let pitch = (offset.y / distance).clamp(-1.0, 1.0).asin();
+        Self {
+            focus,
+            yaw,
+            pitch,
+            distance,
+            yaw_speed: 0.0,
+            pitch_speed: 0.0,
+            pan_speed: Vec3::ZERO,
+            zoom_speed: 0.0,
+            default_pose: (focus, yaw, pitch, distance),
+        }
+    }
+
+    //goes back to the pose the camera was created with
+    pub fn reset(&mut self) {
+        (self.focus, self.yaw, self.pitch, self.distance) = self.default_pose;
+        self.stop();
+    }
+
+    //stops any movement left over from inertia
+    pub fn stop(&mut self) {
+        self.yaw_speed = 0.0;
+        self.pitch_speed = 0.0;
+        self.pan_speed = Vec3::ZERO;
+        self.zoom_speed = 0.0;
+    }
+
+    //camera's view as a bookmark with the given name
+    fn bookmark(&self, name: String, settings: &CameraSettings) -> CameraBookmark {
+        CameraBookmark {
+            name,
+            mode: settings.mode,
+            focus: self.focus.to_array(),
+            yaw: self.yaw,
+            pitch: self.pitch,
+            distance: self.distance,
+            observer_position: settings.observer_position,
+        }
+    }
+
+    //goes to the view saved in a bookmark
+    fn restore(&mut self, bookmark: &CameraBookmark, settings: &mut CameraSettings) {
+        self.focus = Vec3::from_array(bookmark.focus);
+        self.yaw = bookmark.yaw;
+        self.pitch = bookmark.pitch.clamp(-MAX_PITCH, MAX_PITCH);
+        self.distance = bookmark.distance.clamp(MIN_DISTANCE, MAX_DISTANCE);
+        self.stop();
+        settings.mode = bookmark.mode;
+        settings.observer_position = bookmark.observer_position;
+    }
+
+    //rotation of the camera for the current yaw and pitch
+    fn rotation(&self) -> Quat {
+        Quat::from_euler(EulerRot::YXZ, self.yaw, -self.pitch, 0.0)
+    }
+
+    //transform of the camera for the current pose
+    pub fn transform(&self) -> Transform {
+        let rotation = self.rotation();
+        Transform::from_translation(self.focus + rotation * Vec3::Z * self.distance).with_rotation(rotation)
+    }
+}
+
+//a saved camera view
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct CameraBookmark {
+    name: String,
+    mode: CameraMode,
+    focus: [f32; 3],
+    yaw: f32,
+    pitch: f32,
+    distance: f32,
+    observer_position: [f32; 3],
+}
+
+//resource that holds the saved camera bookmarks and the outcome of the last save or load for the ui
+#[derive(Resource)]
+pub struct CameraBookmarks {
+    bookmarks: Vec<CameraBookmark>,
+    new_name: String, //name typed in for the next bookmark
+    last_result: Option<Result<String, String>>,
+}
+
+impl Default for CameraBookmarks {
+    fn default() -> Self {
+        let (bookmarks, last_result) = match load_bookmarks() {
+            Ok(bookmarks) => (bookmarks, None),
+            Err(e) => (vec![], Some(Err(e.to_string()))),
+        };
+        Self {
+            bookmarks,
+            new_name: String::new(),
+            last_result,
+        }
+    }
+}
+
+//reads the saved bookmarks, none if the file does not exist yet
+fn load_bookmarks() -> io::Result<Vec<CameraBookmark>> {
+    let json = match fs::read_to_string(BOOKMARK_FILE) {
+        Ok(json) => json,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(io::Error::new(e.kind(), format!("Could not read {}: {}", BOOKMARK_FILE, e))),
+    };
+    serde_json::from_str(&json)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{} is not a bookmark file: {}", BOOKMARK_FILE, e)))
+}
+
+//writes every bookmark to the bookmark file, replacing what was there
+fn save_bookmarks(bookmarks: &[CameraBookmark]) -> io::Result<()> {
+    let json = serde_json::to_string_pretty(bookmarks).map_err(io::Error::other)?;
+    fs::write(BOOKMARK_FILE, json)
+        .map_err(|e| io::Error::new(e.kind(), format!("Could not save {}: {}", BOOKMARK_FILE, e)))
+}
+
+
+// UPDATE SYSTEMS
+
+//camera update system, runs every frame
+//turns mouse and keyboard input into camera speeds, then moves the camera and lets the speeds die down
+//the ground observer does not move, so input is ignored in that mode
+#[allow(clippy::too_many_arguments)]
+pub fn update_orbit_camera(
+    time: Res<Time>,
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse_motion: Res<AccumulatedMouseMotion>,
+    mouse_scroll: Res<AccumulatedMouseScroll>,
+    egui_input: Res<EguiInputCapture>,
+    settings: Res<CameraSettings>,
+    rocket_query: Query<&Transform, (With<Rocket>, Without<OrbitCamera>)>,
+    mut query: Query<(&mut OrbitCamera, &mut Transform)>,
+) {
+    let delta_secs = time.delta_secs();
+    if delta_secs <= 0.0 {
+        return;
+    }
+    let rocket = rocket_query.single().copied().unwrap_or_default();
+    let takes_input = settings.mode != CameraMode::Observer;
+    for (mut camera, mut transform) in &mut query {
+        let camera = &mut *camera;
+        let rotation = camera.rotation();
+        if takes_input && !egui_input.pointer {
+            //while dragging the camera moves exactly with the mouse, the speed is kept for the inertia after letting go
+            if mouse_buttons.pressed(MouseButton::Left) {
+                camera.yaw_speed = -mouse_motion.delta.x * ORBIT_SENSITIVITY / delta_secs;
+                camera.pitch_speed = mouse_motion.delta.y * ORBIT_SENSITIVITY / delta_secs;
+            } else if mouse_buttons.any_pressed([MouseButton::Right, MouseButton::Middle]) {
+                let pan = rotation * Vec3::new(-mouse_motion.delta.x, mouse_motion.delta.y, 0.0);
+                camera.pan_speed = pan * PAN_SENSITIVITY * camera.distance / delta_secs;
+            }
+            let scroll_lines = match mouse_scroll.unit {
+                MouseScrollUnit::Line => mouse_scroll.delta.y,
+                MouseScrollUnit::Pixel => mouse_scroll.delta.y / PIXELS_PER_SCROLL_LINE,
+            };
+            camera.zoom_speed += scroll_lines * ZOOM_SENSITIVITY;
+        }
+        if takes_input && !egui_input.keyboard {
+            let key_axis = |negative: KeyCode, positive: KeyCode| {
+                keys.pressed(positive) as i32 as f32 - keys.pressed(negative) as i32 as f32
+            };
+            let orbit = Vec2::new(key_axis(KeyCode::ArrowRight, KeyCode::ArrowLeft), key_axis(KeyCode::ArrowDown, KeyCode::ArrowUp));
+            if orbit != Vec2::ZERO {
+                camera.yaw_speed = orbit.x * KEY_ORBIT_SPEED;
+                camera.pitch_speed = orbit.y * KEY_ORBIT_SPEED;
+            }
+            let pan = Vec2::new(key_axis(KeyCode::KeyA, KeyCode::KeyD), key_axis(KeyCode::KeyS, KeyCode::KeyW));
+            if pan != Vec2::ZERO {
+                camera.pan_speed = rotation * Vec3::new(pan.x, pan.y, 0.0) * KEY_PAN_SPEED * camera.distance;
+            }
+            let zoom = key_axis(KeyCode::PageDown, KeyCode::PageUp);
+            if zoom != 0.0 {
+                camera.zoom_speed = zoom * KEY_ZOOM_SPEED;
+            }
+        }
+
+        //move by the current speeds
+        camera.yaw += camera.yaw_speed * delta_secs;
+        camera.pitch = (camera.pitch + camera.pitch_speed * delta_secs).clamp(-MAX_PITCH, MAX_PITCH);
+        camera.focus += camera.pan_speed * delta_secs;
+        camera.distance = (camera.distance * (-camera.zoom_speed * delta_secs).exp()).clamp(MIN_DISTANCE, MAX_DISTANCE);
+
+        //slow down, scaled by the frame time so the inertia feels the same at any frame rate
+        let decay = (-delta_secs / INERTIA_TIME).exp();
+        camera.yaw_speed *= decay;
+        camera.pitch_speed *= decay;
+        camera.pan_speed *= decay;
+        camera.zoom_speed *= decay;
+
+        *transform = match settings.mode {
+            CameraMode::Fixed => camera.transform(),
+            //the orbit pose is in the body frame, carried along with the rocket's position and rotation
+            CameraMode::Chase => rocket * camera.transform(),
+            CameraMode::Observer => Transform::from_translation(Vec3::from_array(settings.observer_position))
+                .looking_at(rocket.translation, Vec3::Y),
+        };
+    }
+}
+
+
+// UI SYSTEMS
+
+//input capture ui system, runs every frame
+//records whether egui is using the mouse or keyboard so the camera leaves them alone
+pub fn ui_system_input_capture(
+    mut contexts: EguiContexts,
+    mut egui_input: ResMut<EguiInputCapture>,
+) -> Result<(), BevyError> {
+    let ctx = contexts.ctx_mut()?;
+    egui_input.pointer = ctx.is_pointer_over_area() || ctx.is_using_pointer();
+    egui_input.keyboard = ctx.wants_keyboard_input();
+    Ok(())
+}
+
+//camera ui system, runs every frame
+//camera mode, the ground observer's position, resetting the view, and saving and going to bookmarks
+pub fn ui_system_camera(
+    mut contexts: EguiContexts,
+    mut settings: ResMut<CameraSettings>,
+    mut bookmarks: ResMut<CameraBookmarks>,
+    mut query: Query<&mut OrbitCamera>,
+) -> Result<(), BevyError> {
+    let ctx = contexts.ctx_mut()?;
+    let Ok(mut camera) = query.single_mut() else {
+        return Ok(());
+    };
+    egui::Window::new("Camera")
+        .default_width(250.0)
+        .default_open(false)
+        .show(ctx, |ui| {
+            let settings = &mut *settings;
+            let bookmarks = &mut *bookmarks;
+            ui.horizontal(|ui| {
+                ui.label("Mode:");
+                egui::ComboBox::from_id_salt("camera_mode")
+                    .selected_text(settings.mode.label())
+                    .show_ui(ui, |ui| {
+                        for mode in CameraMode::ALL {
+                            ui.selectable_value(&mut settings.mode, mode, mode.label());
+                        }
+                    });
+            });
+            if settings.mode == CameraMode::Observer {
+                ui.horizontal(|ui| {
+                    ui.label("Position from Pad:");
+                    for (axis, coordinate) in ["x ", "y ", "z "].iter().zip(settings.observer_position.iter_mut()) {
+                        ui.add(egui::DragValue::new(coordinate).speed(0.05).prefix(*axis));
+                    }
+                });
+            } else {
+                ui.label("Left drag or arrow keys to orbit, right drag or WASD to pan, scroll or Page Up/Down to zoom");
+            }
+            if ui.button("Reset View").clicked() {
+                camera.reset();
+                settings.observer_position = DEFAULT_OBSERVER_POSITION;
+            }
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.label("Bookmark:");
+                ui.text_edit_singleline(&mut bookmarks.new_name);
+                if ui.add_enabled(!bookmarks.new_name.trim().is_empty(), egui::Button::new("Save")).clicked() {
+                    let name = bookmarks.new_name.trim().to_string();
+                    let bookmark = camera.bookmark(name.clone(), settings);
+                    //saving under a name that is already taken replaces that bookmark
+                    match bookmarks.bookmarks.iter_mut().find(|existing| existing.name == name) {
+                        Some(existing) => *existing = bookmark,
+                        None => bookmarks.bookmarks.push(bookmark),
+                    }
+                    bookmarks.last_result = Some(save_bookmarks(&bookmarks.bookmarks)
+                        .map(|()| format!("Saved {}", name))
+                        .map_err(|e| e.to_string()));
+                }
+            });
+            let mut deleted = None;
+            for (index, bookmark) in bookmarks.bookmarks.iter().enumerate() {
+                ui.horizontal(|ui| {
+                    if ui.button("Go").clicked() {
+                        camera.restore(bookmark, settings);
+                    }
+                    if ui.button("Delete").clicked() {
+                        deleted = Some(index);
+                    }
+                    ui.label(format!("{} ({})", bookmark.name, bookmark.mode.label()));
+                });
+            }
+            if let Some(index) = deleted {
+                let bookmark = bookmarks.bookmarks.remove(index);
+                bookmarks.last_result = Some(save_bookmarks(&bookmarks.bookmarks)
+                    .map(|()| format!("Deleted {}", bookmark.name))
+                    .map_err(|e| e.to_string()));
+            }
+            match &bookmarks.last_result {
+                Some(Ok(message)) => {
+                    ui.label(message);
+                }
+                Some(Err(message)) => {
+                    ui.colored_label(egui::Color32::LIGHT_RED, message);
+                }
+                None => (),
+            }
+        });
+    Ok(())
+}
@@ -0,0 +1,305 @@
+//euler angle readout, the orientation as three angles in a rotation order the user picks instead of a quaternion
+//only the tait-bryan orders are offered, they all lock up when the middle angle gets to 90 degrees so the readout warns near that
+//
+//the euler angles are about the y up world's axes, so none of them is the rocket's roll or heading,
+//those come from the rocket attitude instead, worked out around the rocket's long axis which is body y
+
+use std::f32::consts::{PI, TAU};
+use bevy::prelude::*;
+use bevy_egui::egui;
+
+const GIMBAL_LOCK_WARNING_ANGLE: f32 = 85.0; //degrees, the middle angle past this gets the gimbal lock warning
+
+//orders the three rotations can be applied in, the first axis is rotated about first
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum EulerOrder {
+    Xyz,
+    Xzy,
+    Yxz,
+    Yzx,
+    Zxy,
+    Zyx,
+}
+
+impl EulerOrder {
+    //every order in the order they are shown in the dropdown
+    pub const ALL: [EulerOrder; 6] = [
+        EulerOrder::Zyx,
+        EulerOrder::Xyz,
+        EulerOrder::Xzy,
+        EulerOrder::Yxz,
+        EulerOrder::Yzx,
+        EulerOrder::Zxy,
+    ];
+
+    //name shown in the order dropdown
+    pub fn label(&self) -> &'static str {
+        match self {
+            EulerOrder::Xyz => "XYZ",
+            EulerOrder::Xzy => "XZY",
+            EulerOrder::Yxz => "YXZ",
+            EulerOrder::Yzx => "YZX",
+            EulerOrder::Zxy => "ZXY",
+            EulerOrder::Zyx => "ZYX",
+        }
+    }
+
+    //axis of each of the three angles, in the order they are applied
+    pub fn axes(&self) -> [&'static str; 3] {
+        match self {
+            EulerOrder::Xyz => ["X", "Y", "Z"],
+            EulerOrder::Xzy => ["X", "Z", "Y"],
+            EulerOrder::Yxz => ["Y", "X", "Z"],
+            EulerOrder::Yzx => ["Y", "Z", "X"],
+            EulerOrder::Zxy => ["Z", "X", "Y"],
+            EulerOrder::Zyx => ["Z", "Y", "X"],
+        }
+    }
+
+    //bevy's name for the same order
+    fn rotation(&self) -> EulerRot {
+        match self {
+            EulerOrder::Xyz => EulerRot::XYZ,
+            EulerOrder::Xzy => EulerRot::XZY,
+            EulerOrder::Yxz => EulerRot::YXZ,
+            EulerOrder::Yzx => EulerRot::YZX,
+            EulerOrder::Zxy => EulerRot::ZXY,
+            EulerOrder::Zyx => EulerRot::ZYX,
+        }
+    }
+}
+
+//units the angles can be shown in
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum AngleUnit {
+    Degrees,
+    Radians,
+}
+
+impl AngleUnit {
+    //every unit in the order they are shown in the dropdown
+    pub const ALL: [AngleUnit; 2] = [
+        AngleUnit::Degrees,
+        AngleUnit::Radians,
+    ];
+
+    //name shown in the unit dropdown
+    pub fn label(&self) -> &'static str {
+        match self {
+            AngleUnit::Degrees => "Degrees",
+            AngleUnit::Radians => "Radians",
+        }
+    }
+
+    //converts an angle in radians to this unit
+    fn convert(&self, radians: f32) -> f32 {
+        match self {
+            AngleUnit::Degrees => radians.to_degrees(),
+            AngleUnit::Radians => radians,
+        }
+    }
+
+    //written after each angle
+    fn suffix(&self) -> &'static str {
+        match self {
+            AngleUnit::Degrees => "°",
+            AngleUnit::Radians => " rad",
+        }
+    }
+}
+
+//resource that holds the euler readout settings
+#[derive(Resource, Debug)]
+pub struct EulerSettings {
+    pub order: EulerOrder,
+    pub unit: AngleUnit,
+}
+
+impl Default for EulerSettings {
+    fn default() -> Self {
+        Self {
+            order: EulerOrder::Zyx,
+            unit: AngleUnit::Degrees,
+        }
+    }
+}
+
+//the three euler angles of the orientation in radians, in the order they are applied
+pub fn euler_angles(quat: Quat, order: EulerOrder) -> [f32; 3] {
+    let (first, second, third) = quat.normalize().to_euler(order.rotation());
+    [first, second, third]
+}
+
+//attitude of the rocket the way a rocket's orientation is usually described, in radians
+pub struct RocketAttitude {
+    pub tilt: f32, //how far the long axis leans off vertical, 0 standing straight up
+    pub heading: f32, //direction the nose leans toward, about world y from +z toward +x, 0 while standing straight up
+    pub roll: f32, //turn about the long axis, -pi to pi
+}
+
+//splits the orientation into the lean of the long axis and the turn about it
+//the roll is the twist about body y left over once the shortest rotation taking world y to the nose is taken out,
+//so it stays meaningful while the rocket stands upright, where yaw and roll in a yaw pitch roll order would lock up
+pub fn rocket_attitude(quat: Quat) -> RocketAttitude {
+    let quat = quat.normalize();
+    let nose = quat * Vec3::Y;
+    let twist = 2.0 * quat.y.atan2(quat.w);
+    RocketAttitude {
+        tilt: nose.angle_between(Vec3::Y),
+        heading: if nose.x == 0.0 && nose.z == 0.0 { 0.0 } else { nose.x.atan2(nose.z) },
+        //atan2 of the half angle can give up to 2 pi either way, bring it back into -pi to pi
+        roll: (twist + PI).rem_euclid(TAU) - PI,
+    }
+}
+
+//true if the orientation is close enough to gimbal lock that the first and third angles stop meaning much
+//happens when the middle angle gets close to 90 degrees either way
+pub fn near_gimbal_lock(quat: Quat, order: EulerOrder) -> bool {
+    euler_angles(quat, order)[1].to_degrees().abs() >= GIMBAL_LOCK_WARNING_ANGLE
+}
+
+//euler readout and its settings, shown in the monitor window
+pub fn show_euler_readout(ui: &mut egui::Ui, settings: &mut EulerSettings, quat: Quat) {
+    ui.horizontal(|ui| {
+        ui.label("Euler:");
+        egui::ComboBox::from_id_salt("euler_order")
+            .selected_text(settings.order.label())
+            .show_ui(ui, |ui| {
+                for order in EulerOrder::ALL {
+                    ui.selectable_value(&mut settings.order, order, order.label());
+                }
+            });
+        egui::ComboBox::from_id_salt("euler_unit")
+            .selected_text(settings.unit.label())
+            .show_ui(ui, |ui| {
+                for unit in AngleUnit::ALL {
+                    ui.selectable_value(&mut settings.unit, unit, unit.label());
+                }
+            });
+    });
+    let angles = euler_angles(quat, settings.order);
+    let readout: Vec<String> = settings.order.axes().iter()
+        .zip(angles)
+        .map(|(axis, angle)| format!("{}: {:.2}{}", axis, settings.unit.convert(angle), settings.unit.suffix()))
+        .collect();
+    ui.label(readout.join("  "));
+    if near_gimbal_lock(quat, settings.order) {
+        ui.colored_label(egui::Color32::YELLOW, format!("Near gimbal lock, the {} and {} angles are unreliable", settings.order.axes()[0], settings.order.axes()[2]));
+    }
+    let attitude = rocket_attitude(quat);
+    let unit = settings.unit;
+    ui.label(format!(
+        "Tilt: {:.2}{}  Heading: {:.2}{}  Roll: {:.2}{}",
+        unit.convert(attitude.tilt), unit.suffix(),
+        unit.convert(attitude.heading), unit.suffix(),
+        unit.convert(attitude.roll), unit.suffix(),
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_PI_2;
+    use super::*;
+
+    const TOLERANCE: f32 = 1e-4;
+
+    //unit vector for an axis name returned by EulerOrder::axes
+    fn axis_vector(axis: &str) -> Vec3 {
+        match axis {
+            "X" => Vec3::X,
+            "Y" => Vec3::Y,
+            "Z" => Vec3::Z,
+            _ => unreachable!(),
+        }
+    }
+
+    //the rotation the three angles stand for, built by hand so it checks the order as well as the conversion
+    //each rotation is about the axis already turned by the ones before it
+    fn compose(order: EulerOrder, angles: [f32; 3]) -> Quat {
+        order.axes().iter()
+            .zip(angles)
+            .fold(Quat::IDENTITY, |quat, (axis, angle)| quat * Quat::from_axis_angle(axis_vector(axis), angle))
+    }
+
+    fn assert_angles(actual: [f32; 3], expected: [f32; 3], context: &str) {
+        for (actual, expected) in actual.iter().zip(expected) {
+            assert!((actual - expected).abs() < TOLERANCE, "{}: got {:?}, expected {:?}", context, actual, expected);
+        }
+    }
+
+    #[test]
+    fn ninety_degrees_about_a_single_axis() {
+        for order in EulerOrder::ALL {
+            for (index, axis) in order.axes().iter().enumerate() {
+                let quat = Quat::from_axis_angle(axis_vector(axis), FRAC_PI_2);
+                let angles = euler_angles(quat, order);
+                if index == 1 {
+                    //90 degrees about the middle axis is gimbal lock, only the middle angle can be checked
+                    //and only loosely, the arcsine it comes from is very sensitive right at 90 degrees
+                    assert!((angles[1].to_degrees() - 90.0).abs() < 0.1, "{} about {}: {:?}", order.label(), axis, angles);
+                    assert!(near_gimbal_lock(quat, order), "{} about {}", order.label(), axis);
+                } else {
+                    let mut expected = [0.0; 3];
+                    expected[index] = FRAC_PI_2;
+                    assert_angles(angles, expected, &format!("{} about {}", order.label(), axis));
+                    assert!(!near_gimbal_lock(quat, order), "{} about {}", order.label(), axis);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn combined_rotations_round_trip_for_every_order() {
+        let cases = [
+            [30.0_f32, -20.0, 50.0],
+            [-120.0, 45.0, 170.0],
+            [10.0, -80.0, -95.0],
+        ];
+        for order in EulerOrder::ALL {
+            for degrees in cases {
+                let radians = degrees.map(f32::to_radians);
+                let quat = compose(order, radians);
+                let angles = euler_angles(quat, order);
+                let context = format!("{} {:?}", order.label(), degrees);
+                assert_angles(angles, radians, &context);
+                assert_angles(angles.map(|angle| AngleUnit::Degrees.convert(angle)), degrees, &context);
+                assert_angles(angles.map(|angle| AngleUnit::Radians.convert(angle)), radians, &context);
+                assert!(!near_gimbal_lock(quat, order), "{}", context);
+            }
+        }
+    }
+
+    #[test]
+    fn gimbal_lock_warning_starts_at_the_warning_angle() {
+        for order in EulerOrder::ALL {
+            let middle = axis_vector(order.axes()[1]);
+            let just_inside = Quat::from_axis_angle(middle, (GIMBAL_LOCK_WARNING_ANGLE - 1.0).to_radians());
+            let just_past = Quat::from_axis_angle(middle, -(GIMBAL_LOCK_WARNING_ANGLE + 1.0).to_radians());
+            assert!(!near_gimbal_lock(just_inside, order), "{}", order.label());
+            assert!(near_gimbal_lock(just_past, order), "{}", order.label());
+        }
+    }
+
+    #[test]
+    fn angle_units() {
+        assert!((AngleUnit::Degrees.convert(FRAC_PI_2) - 90.0).abs() < TOLERANCE);
+        assert_eq!(AngleUnit::Radians.convert(FRAC_PI_2), FRAC_PI_2);
+        assert_eq!(AngleUnit::Degrees.suffix(), "°");
+        assert_eq!(AngleUnit::Radians.suffix(), " rad");
+    }
+
+    #[test]
+    fn rocket_attitude_of_known_rotations() {
+        //standing upright and rolled about the long axis
+        let attitude = rocket_attitude(Quat::from_rotation_y(40.0_f32.to_radians()));
+        assert_angles([attitude.tilt, attitude.heading, attitude.roll], [0.0, 0.0, 40.0_f32.to_radians()], "rolled upright");
+        //leaning 30 degrees toward +x, then rolled 45 degrees about the long axis
+        let quat = Quat::from_rotation_z(-30.0_f32.to_radians()) * Quat::from_rotation_y(45.0_f32.to_radians());
+        let attitude = rocket_attitude(quat);
+        assert_angles([attitude.tilt, attitude.heading, attitude.roll], [30.0, 90.0, 45.0].map(f32::to_radians), "leaning toward +x");
+        //leaning toward -z, rolled the other way past 180 degrees so it wraps
+        let quat = Quat::from_rotation_x(-60.0_f32.to_radians()) * Quat::from_rotation_y(-200.0_f32.to_radians());
+        let attitude = rocket_attitude(quat);
+        assert_angles([attitude.tilt, attitude.heading.abs(), attitude.roll], [60.0, 180.0, 160.0].map(f32::to_radians), "leaning toward -z");
+    }
+}
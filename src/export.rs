@@ -0,0 +1,279 @@
+//csv export of recorded sessions, for opening flight data in spreadsheets or pandas
+//one row per parsed sample with the device time, host time, quaternion, euler angles, and any extra fields the board sent
+//lines that could not be parsed have no data to put in a row so they are left out
+//
+//the euler angles are worked out from the world frame orientation, after the frame mapping and calibration like the readout,
+//and the column names say so along with the rotation order, for example world_zyx_z_deg
+//the export runs on its own thread so a long recording does not freeze the ui
+
+use std::collections::BTreeSet;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+use bevy::prelude::*;
+use bevy_egui::{EguiContexts, egui};
+use serde_json::Value;
+use crate::{ArduinoData, SerialMonitorSelection};
+use crate::calibration::{Calibration, WorldTransform};
+use crate::euler::{EulerOrder, euler_angles};
+use crate::frame::FrameMapping;
+use crate::session_log::{LogRecord, load_session};
+
+//characters that can separate the columns
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum CsvDelimiter {
+    Comma,
+    Semicolon, //what spreadsheets expect in locales that use a comma as the decimal separator
+    Tab,
+}
+
+impl CsvDelimiter {
+    //every delimiter in the order they are shown in the dropdown
+    pub const ALL: [CsvDelimiter; 3] = [
+        CsvDelimiter::Comma,
+        CsvDelimiter::Semicolon,
+        CsvDelimiter::Tab,
+    ];
+
+    //name shown in the delimiter dropdown
+    pub fn label(&self) -> &'static str {
+        match self {
+            CsvDelimiter::Comma => "Comma",
+            CsvDelimiter::Semicolon => "Semicolon",
+            CsvDelimiter::Tab => "Tab",
+        }
+    }
+
+    //character written between fields
+    fn as_char(&self) -> char {
+        match self {
+            CsvDelimiter::Comma => ',',
+            CsvDelimiter::Semicolon => ';',
+            CsvDelimiter::Tab => '\t',
+        }
+    }
+}
+
+//which groups of columns go in the export and how they are separated
+#[derive(Debug, Clone)]
+pub struct CsvOptions {
+    pub device_time: bool, //time field sent by the board, milliseconds
+    pub host_time: bool, //when the line was received, microseconds since the session started and wall clock milliseconds
+    pub quaternion: bool, //x, y, z, w
+    pub euler: bool, //euler angles of the world frame orientation in degrees
+    pub euler_order: EulerOrder,
+    pub extra: bool, //one column for every other field that shows up anywhere in the session
+    pub delimiter: CsvDelimiter,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        Self {
+            device_time: true,
+            host_time: true,
+            quaternion: true,
+            euler: true,
+            euler_order: EulerOrder::Zyx,
+            extra: true,
+            delimiter: CsvDelimiter::Comma,
+        }
+    }
+}
+
+//resource that holds the export options and the outcome of the last export for the ui
+//the receiver is wrapped in a mutex since resources have to be sync
+#[derive(Resource, Default)]
+pub struct CsvExport {
+    options: CsvOptions,
+    running: Option<Mutex<Receiver<Result<String, String>>>>, //some while an export thread is working, it sends its outcome when done
+    last_result: Option<Result<String, String>>,
+}
+
+//reads the session file at session_path and writes it to output_path as csv
+//to_world turns a sample into its world frame orientation for the euler columns
+//returns how many rows were written
+pub fn export_csv(session_path: &Path, output_path: &Path, options: &CsvOptions, to_world: impl Fn(&ArduinoData) -> Quat) -> io::Result<usize> {
+    if session_path == output_path {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Exporting {} would overwrite it", session_path.display())));
+    }
+    let session = load_session(session_path)?;
+    let file = File::create(output_path)
+        .map_err(|e| io::Error::new(e.kind(), format!("Could not create {}: {}", output_path.display(), e)))?;
+    let mut writer = BufWriter::new(file);
+    let rows = write_csv(&mut writer, &session.records, options, to_world)?;
+    writer.flush()?;
+    Ok(rows)
+}
+
+//writes the header row and one row per parsed record
+fn write_csv<W: Write>(writer: &mut W, records: &[LogRecord], options: &CsvOptions, to_world: impl Fn(&ArduinoData) -> Quat) -> io::Result<usize> {
+    //extra fields can come and go during a session, every one that shows up gets a column, sorted so the order is stable
+    let extra_columns: BTreeSet<&str> = if options.extra {
+        records.iter()
+            .filter_map(|record| record.data.as_ref())
+            .flat_map(|data| data.extra.keys().map(String::as_str))
+            .collect()
+    } else {
+        BTreeSet::new()
+    };
+
+    let mut header: Vec<String> = vec![];
+    if options.device_time {
+        header.push("time".to_string());
+    }
+    if options.host_time {
+        header.extend(["host_time_us", "wall_time_ms"].map(String::from));
+    }
+    if options.quaternion {
+        header.extend(["x", "y", "z", "w"].map(String::from));
+    }
+    if options.euler {
+        let order = options.euler_order.label().to_lowercase();
+        header.extend(options.euler_order.axes().map(|axis| format!("world_{}_{}_deg", order, axis.to_lowercase())));
+    }
+    header.extend(extra_columns.iter().map(|column| column.to_string()));
+    write_row(writer, &header, options.delimiter)?;
+
+    let mut rows = 0;
+    for record in records {
+        let Some(data) = &record.data else {
+            continue;
+        };
+        let mut row: Vec<String> = vec![];
+        if options.device_time {
+            row.push(data.time.to_string());
+        }
+        if options.host_time {
+            row.push(record.host_time_us.to_string());
+            row.push(record.wall_time_ms.to_string());
+        }
+        if options.quaternion {
+            row.extend([data.x, data.y, data.z, data.w].map(|component| component.to_string()));
+        }
+        if options.euler {
+            row.extend(euler_angles(to_world(data), options.euler_order).map(|angle| angle.to_degrees().to_string()));
+        }
+        for column in &extra_columns {
+            //fields missing from this record are left empty, strings are written without their json quotes
+            row.push(match data.extra.get(*column) {
+                Some(Value::String(text)) => text.clone(),
+                Some(value) => value.to_string(),
+                None => String::new(),
+            });
+        }
+        write_row(writer, &row, options.delimiter)?;
+        rows += 1;
+    }
+    Ok(rows)
+}
+
+//writes a single row, fields holding the delimiter, quotes, or line breaks are quoted
+fn write_row<W: Write>(writer: &mut W, fields: &[String], delimiter: CsvDelimiter) -> io::Result<()> {
+    let delimiter = delimiter.as_char();
+    let fields: Vec<String> = fields.iter()
+        .map(|field| {
+            if field.contains([delimiter, '"', '\n', '\r']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field.clone()
+            }
+        })
+        .collect();
+    writeln!(writer, "{}", fields.join(&delimiter.to_string()))
+}
+
+//the csv file goes next to the session file with the same name
+fn output_path_for(session_path: &str) -> PathBuf {
+    Path::new(session_path).with_extension("csv")
+}
+
+
+// UI SYSTEMS
+
+//export ui system, runs every frame
+//exports the recording selected in the main window with the chosen columns and delimiter on a background thread,
+//and picks up the outcome once the thread is done
+pub fn ui_system_export(
+    mut contexts: EguiContexts,
+    selection: Res<SerialMonitorSelection>,
+    frame_mapping: Res<FrameMapping>,
+    calibration: Res<Calibration>,
+    mut export: ResMut<CsvExport>,
+) -> Result<(), BevyError> {
+    let ctx = contexts.ctx_mut()?;
+    let finished = match export.running.as_ref().map(|receiver| receiver.lock().map(|receiver| receiver.try_recv())) {
+        Some(Ok(Ok(result))) => Some(result),
+        //the thread went away without sending anything
+        Some(Ok(Err(TryRecvError::Disconnected))) | Some(Err(_)) => Some(Err("The export stopped unexpectedly".to_string())),
+        Some(Ok(Err(TryRecvError::Empty))) | None => None,
+    };
+    if let Some(result) = finished {
+        export.running = None;
+        export.last_result = Some(result);
+    }
+    egui::Window::new("CSV Export")
+        .default_width(200.0)
+        .default_open(false)
+        .show(ctx, |ui| {
+            ui.label(format!("Recording: {}", selection.replay_path));
+            let options = &mut export.options;
+            ui.checkbox(&mut options.device_time, "Device time");
+            ui.checkbox(&mut options.host_time, "Host time");
+            ui.checkbox(&mut options.quaternion, "Quaternion");
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut options.euler, "Euler angles");
+                egui::ComboBox::from_id_salt("csv_euler_order")
+                    .selected_text(options.euler_order.label())
+                    .show_ui(ui, |ui| {
+                        for order in EulerOrder::ALL {
+                            ui.selectable_value(&mut options.euler_order, order, order.label());
+                        }
+                    });
+            });
+            ui.checkbox(&mut options.extra, "Extra fields");
+            ui.horizontal(|ui| {
+                ui.label("Delimiter:");
+                egui::ComboBox::from_id_salt("csv_delimiter")
+                    .selected_text(options.delimiter.label())
+                    .show_ui(ui, |ui| {
+                        for delimiter in CsvDelimiter::ALL {
+                            ui.selectable_value(&mut options.delimiter, delimiter, delimiter.label());
+                        }
+                    });
+            });
+            let exporting = export.running.is_some();
+            if ui.add_enabled(!selection.replay_path.is_empty() && !exporting, egui::Button::new("Export")).clicked() {
+                let session_path = PathBuf::from(&selection.replay_path);
+                let output_path = output_path_for(&selection.replay_path);
+                let options = export.options.clone();
+                let transform = WorldTransform::new(&frame_mapping, &calibration);
+                let (sender, receiver) = mpsc::channel();
+                thread::spawn(move || {
+                    let result = export_csv(&session_path, &output_path, &options, |data| transform.apply(data))
+                        .map(|rows| format!("Wrote {} rows to {}", rows, output_path.display()))
+                        .map_err(|e| e.to_string());
+                    let _ = sender.send(result);
+                });
+                export.running = Some(Mutex::new(receiver));
+            }
+            if exporting {
+                ui.horizontal(|ui| {
+                    ui.spinner();
+                    ui.label("Exporting...");
+                });
+            }
+            match &export.last_result {
+                Some(Ok(message)) => {
+                    ui.label(message);
+                }
+                Some(Err(message)) => {
+                    ui.colored_label(egui::Color32::LIGHT_RED, message);
+                }
+                None => (),
+            }
+        });
+    Ok(())
+}
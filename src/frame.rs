@@ -0,0 +1,425 @@
+//frame mapping, turns the quaternion the imu sends into bevy's y up world before anything else sees it
+//imus usually work in a z up or north east down frame, so without this the model turns about the wrong axes
+//
+//each world axis is picked from one of the sensor axes with a sign, which covers axis swaps, sign flips and handedness,
+//a mapping with an odd number of flips and swaps turns a left handed sensor frame into the right handed world
+//the quaternion component order and the rotation direction can be switched for firmware that sends them differently
+
+use bevy::prelude::*;
+use bevy_egui::{EguiContexts, egui};
+use serde::{Deserialize, Serialize};
+use crate::{ArduinoData, CurrentData};
+use crate::calibration::{Calibration, WorldTransform};
+use crate::history::TelemetryHistory;
+
+//sensor axes a world axis can be taken from
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Eq, PartialEq)]
+pub enum SensorAxis {
+    PlusX,
+    MinusX,
+    PlusY,
+    MinusY,
+    PlusZ,
+    MinusZ,
+}
+
+impl SensorAxis {
+    //every axis in the order they are shown in the dropdowns
+    pub const ALL: [SensorAxis; 6] = [
+        SensorAxis::PlusX,
+        SensorAxis::MinusX,
+        SensorAxis::PlusY,
+        SensorAxis::MinusY,
+        SensorAxis::PlusZ,
+        SensorAxis::MinusZ,
+    ];
+
+    //name shown in the axis dropdowns
+    pub fn label(&self) -> &'static str {
+        match self {
+            SensorAxis::PlusX => "+X",
+            SensorAxis::MinusX => "-X",
+            SensorAxis::PlusY => "+Y",
+            SensorAxis::MinusY => "-Y",
+            SensorAxis::PlusZ => "+Z",
+            SensorAxis::MinusZ => "-Z",
+        }
+    }
+
+    //the axis as a unit vector in the sensor frame
+    fn vector(&self) -> Vec3 {
+        match self {
+            SensorAxis::PlusX => Vec3::X,
+            SensorAxis::MinusX => Vec3::NEG_X,
+            SensorAxis::PlusY => Vec3::Y,
+            SensorAxis::MinusY => Vec3::NEG_Y,
+            SensorAxis::PlusZ => Vec3::Z,
+            SensorAxis::MinusZ => Vec3::NEG_Z,
+        }
+    }
+}
+
+//order the quaternion components come in, the json fields are always called x, y, z and w
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ComponentOrder {
+    Xyzw, //the fields hold what their names say
+    Wxyz, //the fields hold w, x, y, z in that order, for firmware that copies a w first array straight into x, y, z, w
+}
+
+impl ComponentOrder {
+    //every order in the order they are shown in the dropdown
+    pub const ALL: [ComponentOrder; 2] = [
+        ComponentOrder::Xyzw,
+        ComponentOrder::Wxyz,
+    ];
+
+    //name shown in the order dropdown
+    pub fn label(&self) -> &'static str {
+        match self {
+            ComponentOrder::Xyzw => "x, y, z, w",
+            ComponentOrder::Wxyz => "w, x, y, z",
+        }
+    }
+}
+
+//ready made mappings for common imus and frames
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum FramePreset {
+    AsSent, //the firmware already sends y up
+    ZUp, //x right, y forward, z up, also what the bno055 fusion output uses with the chip flat
+    Ned, //x north, y east, z down
+    Mpu6050Dmp, //mpu6050 dmp quaternion, z up, sent in the dmp packet's w first order
+}
+
+impl FramePreset {
+    //every preset in the order they are shown in the dropdown
+    pub const ALL: [FramePreset; 4] = [
+        FramePreset::AsSent,
+        FramePreset::ZUp,
+        FramePreset::Ned,
+        FramePreset::Mpu6050Dmp,
+    ];
+
+    //name shown in the preset dropdown
+    pub fn label(&self) -> &'static str {
+        match self {
+            FramePreset::AsSent => "As Sent (Y Up)",
+            FramePreset::ZUp => "Z Up (BNO055)",
+            FramePreset::Ned => "North East Down",
+            FramePreset::Mpu6050Dmp => "MPU6050 DMP",
+        }
+    }
+
+    //the mapping the preset stands for
+    pub fn mapping(&self) -> FrameMapping {
+        //world x, y and z from the sensor frame
+        let (axes, component_order) = match self {
+            FramePreset::AsSent => ([SensorAxis::PlusX, SensorAxis::PlusY, SensorAxis::PlusZ], ComponentOrder::Xyzw),
+            FramePreset::ZUp => ([SensorAxis::PlusX, SensorAxis::PlusZ, SensorAxis::MinusY], ComponentOrder::Xyzw),
+            FramePreset::Ned => ([SensorAxis::PlusY, SensorAxis::MinusZ, SensorAxis::MinusX], ComponentOrder::Xyzw),
+            FramePreset::Mpu6050Dmp => ([SensorAxis::PlusX, SensorAxis::PlusZ, SensorAxis::MinusY], ComponentOrder::Wxyz),
+        };
+        FrameMapping {
+            axes,
+            component_order,
+            conjugate: false,
+        }
+    }
+}
+
+//resource that holds how samples are turned from the sensor frame into the world frame
+#[derive(Resource, Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FrameMapping {
+    pub axes: [SensorAxis; 3], //sensor axis each of the world x, y and z axes is taken from
+    pub component_order: ComponentOrder,
+    pub conjugate: bool, //the quaternion turns the world into the body instead of the body into the world
+}
+
+impl Default for FrameMapping {
+    fn default() -> Self {
+        FramePreset::AsSent.mapping()
+    }
+}
+
+impl FrameMapping {
+    //matrix that takes a sensor frame vector to the world frame, each row is the sensor axis a world axis comes from
+    fn matrix(&self) -> Mat3 {
+        Mat3::from_cols(self.axes[0].vector(), self.axes[1].vector(), self.axes[2].vector()).transpose()
+    }
+
+    //true if every world axis comes from a different sensor axis
+    pub fn is_valid(&self) -> bool {
+        self.matrix().determinant().abs() > 0.5
+    }
+
+    //true if the mapping mirrors the sensor frame, meaning the sensor frame is left handed compared to the world
+    pub fn is_mirrored(&self) -> bool {
+        self.matrix().determinant() < 0.0
+    }
+
+    //the sample's quaternion in the sensor frame, with the components in the right places and the rotation direction fixed
+    pub fn sensor_quat(&self, data: &ArduinoData) -> Quat {
+        sensor_quat(data, self.component_order, self.conjugate)
+    }
+
+    //the sample's orientation in the world frame
+    pub fn apply(&self, data: &ArduinoData) -> Quat {
+        self.prepare().apply(data)
+    }
+
+    //the mapping with its matrix worked out, for mapping many samples
+    pub fn prepare(&self) -> PreparedMapping {
+        let matrix = self.matrix();
+        PreparedMapping {
+            component_order: self.component_order,
+            conjugate: self.conjugate,
+            matrix: self.is_valid().then_some(matrix),
+            sign: matrix.determinant().signum(),
+        }
+    }
+
+    //the preset this mapping matches, if any
+    fn preset(&self) -> Option<FramePreset> {
+        FramePreset::ALL.into_iter().find(|preset| preset.mapping() == *self)
+    }
+}
+
+//a frame mapping with the matrix and its handedness worked out once, so a whole history can be mapped without redoing them per sample
+#[derive(Debug, Clone, Copy)]
+pub struct PreparedMapping {
+    component_order: ComponentOrder,
+    conjugate: bool,
+    matrix: Option<Mat3>, //none if the mapping is invalid
+    sign: f32, //-1 if the mapping mirrors the sensor frame
+}
+
+impl PreparedMapping {
+    //the sample's orientation in the world frame
+    //the rotation axis is carried over to the world frame like any other vector, but flipped if the mapping mirrors,
+    //since mirroring turns every rotation the other way
+    //an invalid mapping leaves the axes as they are
+    pub fn apply(&self, data: &ArduinoData) -> Quat {
+        let quat = sensor_quat(data, self.component_order, self.conjugate);
+        let Some(matrix) = self.matrix else {
+            return quat.normalize();
+        };
+        let axis = matrix * quat.xyz() * self.sign;
+        Quat::from_xyzw(axis.x, axis.y, axis.z, quat.w).normalize()
+    }
+}
+
+//the sample's quaternion with the components in the right places and the rotation direction fixed
+fn sensor_quat(data: &ArduinoData, component_order: ComponentOrder, conjugate: bool) -> Quat {
+    let quat = match component_order {
+        ComponentOrder::Xyzw => Quat::from_xyzw(data.x, data.y, data.z, data.w),
+        ComponentOrder::Wxyz => Quat::from_xyzw(data.y, data.z, data.w, data.x),
+    };
+    if conjugate { quat.conjugate() } else { quat }
+}
+
+
+// UPDATE SYSTEMS
+
+//frame mapping update system, runs whenever the frame mapping or the calibration changes
+//maps every sample in the history again so the plots, the replay, and the rocket model all use the new mapping
+pub fn remap_history(
+    frame_mapping: Res<FrameMapping>,
+    calibration: Res<Calibration>,
+    mut history: ResMut<TelemetryHistory>,
+    current_data: Option<ResMut<CurrentData>>,
+) {
+    //the mapping and calibration are worked out once here, not once per sample, since this runs every frame while a value is dragged
+    let transform = WorldTransform::new(&frame_mapping, &calibration);
+    history.remap(|data| transform.apply(data));
+    if let Some(mut current_data) = current_data
+        && let Some(sample) = history.at_or_before(current_data.timeline_ms)
+    {
+        current_data.quat = sample.quat;
+    }
+}
+
+
+// UI SYSTEMS
+
+//frame mapping ui system, runs every frame
+//lets the user pick a preset or set up the mapping by hand, and shows the current sample before and after mapping
+pub fn ui_system_frame_mapping(
+    mut contexts: EguiContexts,
+    mut frame_mapping: ResMut<FrameMapping>,
+    history: Res<TelemetryHistory>,
+) -> Result<(), BevyError> {
+    let ctx = contexts.ctx_mut()?;
+    //edit a copy so the mapping only counts as changed, and the history only gets remapped, when something was actually changed
+    let mut mapping = frame_mapping.clone();
+    egui::Window::new("Frame Mapping")
+        .default_width(250.0)
+        .default_open(false)
+        .show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Preset:");
+                egui::ComboBox::from_id_salt("frame_preset")
+                    .selected_text(mapping.preset().map_or("Custom", |preset| preset.label()))
+                    .show_ui(ui, |ui| {
+                        for preset in FramePreset::ALL {
+                            if ui.selectable_label(mapping.preset() == Some(preset), preset.label()).clicked() {
+                                mapping = preset.mapping();
+                            }
+                        }
+                    });
+            });
+            ui.horizontal(|ui| {
+                ui.label("Component Order:");
+                egui::ComboBox::from_id_salt("frame_component_order")
+                    .selected_text(mapping.component_order.label())
+                    .show_ui(ui, |ui| {
+                        for component_order in ComponentOrder::ALL {
+                            ui.selectable_value(&mut mapping.component_order, component_order, component_order.label());
+                        }
+                    });
+            });
+            ui.horizontal(|ui| {
+                for (world_axis, sensor_axis) in ["X", "Y", "Z"].iter().zip(mapping.axes.iter_mut()) {
+                    ui.label(format!("World {} =", world_axis));
+                    egui::ComboBox::from_id_salt(format!("frame_axis_{}", world_axis))
+                        .width(50.0)
+                        .selected_text(sensor_axis.label())
+                        .show_ui(ui, |ui| {
+                            for axis in SensorAxis::ALL {
+                                ui.selectable_value(sensor_axis, axis, axis.label());
+                            }
+                        });
+                }
+            });
+            ui.checkbox(&mut mapping.conjugate, "Conjugate (quaternion turns world into body)");
+            if !mapping.is_valid() {
+                ui.colored_label(egui::Color32::LIGHT_RED, "Every world axis needs a different sensor axis, the axes are not being mapped");
+            } else if mapping.is_mirrored() {
+                ui.label("Sensor frame is left handed, mirrored into the world");
+            } else {
+                ui.label("Sensor frame is right handed");
+            }
+            //preview of the newest sample with the mapping being edited
+            if let Some(sample) = history.latest() {
+                let raw = mapping.sensor_quat(&sample.data);
+                let mapped = mapping.apply(&sample.data);
+                ui.separator();
+                ui.label(format!("Sensor: ({:.3}, {:.3}, {:.3}, {:.3})", raw.x, raw.y, raw.z, raw.w));
+                ui.label(format!("World: ({:.3}, {:.3}, {:.3}, {:.3})", mapped.x, mapped.y, mapped.z, mapped.w));
+            }
+        });
+    frame_mapping.set_if_neq(mapping);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //a sample holding the quaternion in the given component order
+    fn sample(quat: Quat, component_order: ComponentOrder) -> ArduinoData {
+        let [x, y, z, w] = match component_order {
+            ComponentOrder::Xyzw => [quat.x, quat.y, quat.z, quat.w],
+            ComponentOrder::Wxyz => [quat.w, quat.x, quat.y, quat.z],
+        };
+        ArduinoData {
+            x,
+            y,
+            z,
+            w,
+            time: 0,
+            extra: serde_json::Map::new(),
+        }
+    }
+
+    //q and -q are the same rotation
+    fn assert_same_rotation(actual: Quat, expected: Quat) {
+        assert!(actual.dot(expected).abs() > 1.0 - 1e-5, "{:?} is not {:?}", actual, expected);
+    }
+
+    fn assert_vectors_close(actual: Vec3, expected: Vec3) {
+        assert!(actual.abs_diff_eq(expected, 1e-4), "{:?} is not {:?}", actual, expected);
+    }
+
+    #[test]
+    fn z_up_rotation_about_z_turns_about_world_y() {
+        let mapping = FramePreset::ZUp.mapping();
+        assert!(mapping.is_valid() && !mapping.is_mirrored());
+        let world = mapping.apply(&sample(Quat::from_rotation_z(0.7), ComponentOrder::Xyzw));
+        assert_same_rotation(world, Quat::from_rotation_y(0.7));
+        //the other axes, x stays x and sensor y is world -z
+        let world = mapping.apply(&sample(Quat::from_rotation_x(0.4), ComponentOrder::Xyzw));
+        assert_same_rotation(world, Quat::from_rotation_x(0.4));
+        let world = mapping.apply(&sample(Quat::from_rotation_y(0.4), ComponentOrder::Xyzw));
+        assert_same_rotation(world, Quat::from_rotation_z(-0.4));
+    }
+
+    #[test]
+    fn ned_yaw_turns_north_to_east() {
+        let mapping = FramePreset::Ned.mapping();
+        assert!(mapping.is_valid() && !mapping.is_mirrored());
+        //north is sensor x and east is sensor y, in the world they are -z and +x
+        let north = Vec3::NEG_Z;
+        let east = Vec3::X;
+        let yaw = mapping.apply(&sample(Quat::from_rotation_z(std::f32::consts::FRAC_PI_2), ComponentOrder::Xyzw));
+        assert_vectors_close(yaw * north, east);
+        //yaw about down is a turn about world -y
+        assert_same_rotation(yaw, Quat::from_rotation_y(-std::f32::consts::FRAC_PI_2));
+        //pitching nose up about east lifts north towards up
+        let pitch = mapping.apply(&sample(Quat::from_rotation_y(0.3), ComponentOrder::Xyzw));
+        assert_same_rotation(pitch, Quat::from_rotation_x(0.3));
+        assert!((pitch * north).y > 0.0);
+    }
+
+    #[test]
+    fn mpu6050_dmp_reads_w_first_and_maps_z_up() {
+        let mapping = FramePreset::Mpu6050Dmp.mapping();
+        let sensor = Quat::from_rotation_z(0.9);
+        let data = sample(sensor, ComponentOrder::Wxyz);
+        assert_same_rotation(mapping.sensor_quat(&data), sensor);
+        assert_same_rotation(mapping.apply(&data), Quat::from_rotation_y(0.9));
+        //the same rotation sent x first through the z up preset ends up in the same place
+        assert_same_rotation(mapping.apply(&data), FramePreset::ZUp.mapping().apply(&sample(sensor, ComponentOrder::Xyzw)));
+    }
+
+    #[test]
+    fn mirrored_mapping_reverses_the_rotation() {
+        let mapping = FrameMapping {
+            axes: [SensorAxis::PlusX, SensorAxis::PlusY, SensorAxis::MinusZ],
+            ..default()
+        };
+        assert!(mapping.is_valid() && mapping.is_mirrored());
+        let world = mapping.apply(&sample(Quat::from_rotation_x(0.5), ComponentOrder::Xyzw));
+        assert_same_rotation(world, Quat::from_rotation_x(-0.5));
+        //a turn about the flipped axis keeps its direction in the world, the flip and the mirroring cancel out
+        let world = mapping.apply(&sample(Quat::from_rotation_z(0.5), ComponentOrder::Xyzw));
+        assert_same_rotation(world, Quat::from_rotation_z(0.5));
+    }
+
+    #[test]
+    fn conjugate_reverses_the_rotation() {
+        let mapping = FrameMapping {
+            conjugate: true,
+            ..default()
+        };
+        let world = mapping.apply(&sample(Quat::from_rotation_y(0.5), ComponentOrder::Xyzw));
+        assert_same_rotation(world, Quat::from_rotation_y(-0.5));
+    }
+
+    #[test]
+    fn invalid_mapping_passes_the_quaternion_through() {
+        let mapping = FrameMapping {
+            axes: [SensorAxis::PlusX, SensorAxis::MinusX, SensorAxis::PlusZ],
+            ..default()
+        };
+        assert!(!mapping.is_valid());
+        let sensor = Quat::from_euler(EulerRot::XYZ, 0.3, -0.6, 1.1);
+        assert_same_rotation(mapping.apply(&sample(sensor, ComponentOrder::Xyzw)), sensor);
+    }
+
+    #[test]
+    fn presets_are_recognized() {
+        for preset in FramePreset::ALL {
+            assert_eq!(preset.mapping().preset(), Some(preset));
+        }
+    }
+}
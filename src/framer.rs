@@ -0,0 +1,200 @@
+//line framer used by the reader thread to turn the raw byte stream from the serial port into lines
+//reads from the port can end anywhere, in the middle of a line or even in the middle of a multi byte utf-8 character,
+//so the framer keeps whatever is left over after the last newline and joins it with the next chunk of bytes
+
+//stateful line framer, feed it chunks of bytes in the order they were read and it hands back every finished line
+pub struct LineFramer {
+    buffer: Vec<u8>, //bytes of the line that has not been finished yet
+    max_line_length: usize, //lines longer than this are treated as garbage and thrown away
+    discarding: bool, //true while skipping the rest of an overlong line, cleared on the next newline
+    dropped_bytes: u64, //total number of bytes thrown away because they were garbage
+}
+
+impl LineFramer {
+    //creates an empty framer that throws away any line longer than max_line_length bytes
+    pub fn new(max_line_length: usize) -> Self {
+        Self {
+            buffer: Vec::new(),
+            max_line_length,
+            discarding: false,
+            dropped_bytes: 0,
+        }
+    }
+
+    //total number of bytes that have been thrown away since the framer was created
+    //this counts overlong lines, lines that are not valid utf-8, and their newlines
+    pub fn dropped_bytes(&self) -> u64 {
+        self.dropped_bytes
+    }
+
+    //throws away the unfinished line, used when the stream is interrupted and the rest of the line will never arrive
+    //the thrown away bytes are counted as dropped
+    pub fn clear(&mut self) {
+        self.dropped_bytes += self.buffer.len() as u64;
+        self.buffer.clear();
+        self.discarding = false;
+    }
+
+    //feeds a chunk of bytes into the framer and returns every line that was finished by it, in order
+    //returned lines have the newline and surrounding whitespace trimmed, empty lines are skipped
+    pub fn push(&mut self, mut bytes: &[u8]) -> Vec<String> {
+        let mut lines = Vec::new();
+        while !bytes.is_empty() {
+            match bytes.iter().position(|&byte| byte == b'\n') {
+                Some(newline) => {
+                    let segment = &bytes[..newline];
+                    bytes = &bytes[newline + 1..];
+                    //the newline ends whatever garbage we were skipping, start fresh after it
+                    if self.discarding {
+                        self.dropped_bytes += segment.len() as u64 + 1;
+                        self.discarding = false;
+                        continue;
+                    }
+                    if self.buffer.len() + segment.len() > self.max_line_length {
+                        self.dropped_bytes += (self.buffer.len() + segment.len()) as u64 + 1;
+                        self.buffer.clear();
+                        continue;
+                    }
+                    self.buffer.extend_from_slice(segment);
+                    let line = std::mem::take(&mut self.buffer);
+                    if let Some(line) = self.finish_line(line) {
+                        lines.push(line);
+                    }
+                }
+                None => {
+                    //no newline left in this chunk, keep the bytes around for the next one
+                    if self.discarding {
+                        self.dropped_bytes += bytes.len() as u64;
+                    } else if self.buffer.len() + bytes.len() > self.max_line_length {
+                        //line is already too long, throw it away and skip everything up to the next newline
+                        self.dropped_bytes += (self.buffer.len() + bytes.len()) as u64;
+                        self.buffer.clear();
+                        self.discarding = true;
+                    } else {
+                        self.buffer.extend_from_slice(bytes);
+                    }
+                    break;
+                }
+            }
+        }
+        lines
+    }
+
+    //turns the bytes of a finished line into a string, returns none if the line was empty or not valid utf-8
+    fn finish_line(&mut self, line: Vec<u8>) -> Option<String> {
+        let line_length = line.len() as u64;
+        match String::from_utf8(line) {
+            Ok(line) => {
+                let line = line.trim();
+                if line.is_empty() {
+                    None
+                } else {
+                    Some(line.to_string())
+                }
+            }
+            Err(_) => {
+                //count the newline too so the dropped bytes add up to what was actually received
+                self.dropped_bytes += line_length + 1;
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn line_split_across_pushes() {
+        let mut framer = LineFramer::new(64);
+        assert!(framer.push(b"{\"x\":0.5,").is_empty());
+        assert_eq!(framer.push(b"\"w\":1}\n"), vec!["{\"x\":0.5,\"w\":1}"]);
+        assert_eq!(framer.dropped_bytes(), 0);
+    }
+
+    #[test]
+    fn every_split_point_gives_the_same_lines() {
+        let stream = b"first\nsecond line\nthird\n";
+        for split in 0..=stream.len() {
+            let mut framer = LineFramer::new(64);
+            let mut lines = framer.push(&stream[..split]);
+            lines.extend(framer.push(&stream[split..]));
+            assert_eq!(lines, vec!["first", "second line", "third"], "split at {}", split);
+        }
+    }
+
+    #[test]
+    fn multi_byte_character_split_across_pushes() {
+        //° is two bytes in utf-8, split it down the middle
+        let line = "tilt 12°\n".as_bytes();
+        let split = line.iter().position(|&byte| byte == 0xc2).unwrap() + 1;
+        let mut framer = LineFramer::new(64);
+        assert!(framer.push(&line[..split]).is_empty());
+        assert_eq!(framer.push(&line[split..]), vec!["tilt 12°"]);
+        assert_eq!(framer.dropped_bytes(), 0);
+    }
+
+    #[test]
+    fn carriage_return_line_endings() {
+        let mut framer = LineFramer::new(64);
+        assert_eq!(framer.push(b"one\r\ntwo\r"), vec!["one"]);
+        assert_eq!(framer.push(b"\n\r\n"), vec!["two"]);
+    }
+
+    #[test]
+    fn overlong_line_is_dropped_and_framer_resyncs() {
+        let mut framer = LineFramer::new(8);
+        //too long within one push
+        assert_eq!(framer.push(b"0123456789\nok\n"), vec!["ok"]);
+        assert_eq!(framer.dropped_bytes(), 11);
+        //too long across pushes, everything up to the next newline is skipped
+        assert!(framer.push(b"012345").is_empty());
+        assert!(framer.push(b"6789").is_empty());
+        assert!(framer.push(b"abc").is_empty());
+        assert_eq!(framer.push(b"def\nback\n"), vec!["back"]);
+        assert_eq!(framer.dropped_bytes(), 11 + 17);
+    }
+
+    #[test]
+    fn line_of_exactly_max_length_is_kept() {
+        let mut framer = LineFramer::new(8);
+        assert_eq!(framer.push(b"01234567\n"), vec!["01234567"]);
+        assert_eq!(framer.dropped_bytes(), 0);
+    }
+
+    #[test]
+    fn garbage_followed_by_a_valid_line() {
+        let mut framer = LineFramer::new(64);
+        assert_eq!(framer.push(b"\xff\xfe\x00junk\n{\"x\":1}\n"), vec!["{\"x\":1}"]);
+        assert_eq!(framer.dropped_bytes(), 8);
+    }
+
+    #[test]
+    fn dropped_bytes_add_up_over_several_kinds_of_garbage() {
+        let mut framer = LineFramer::new(4);
+        assert!(framer.push(b"toolong\n").is_empty()); //8
+        assert!(framer.push(b"\xff\n").is_empty()); //2
+        assert_eq!(framer.push(b"a\n\nb\n"), vec!["a", "b"]); //empty lines are not garbage
+        assert_eq!(framer.dropped_bytes(), 10);
+    }
+
+    #[test]
+    fn clear_drops_the_unfinished_line() {
+        let mut framer = LineFramer::new(64);
+        assert!(framer.push(b"half a li").is_empty());
+        framer.clear();
+        assert_eq!(framer.dropped_bytes(), 9);
+        assert_eq!(framer.push(b"ne\nwhole\n"), vec!["ne", "whole"]);
+        assert_eq!(framer.dropped_bytes(), 9);
+    }
+
+    #[test]
+    fn clear_ends_discarding() {
+        let mut framer = LineFramer::new(4);
+        assert!(framer.push(b"toolong").is_empty());
+        framer.clear();
+        assert_eq!(framer.push(b"ok\n"), vec!["ok"]);
+        assert_eq!(framer.dropped_bytes(), 7);
+    }
+}
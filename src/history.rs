@@ -0,0 +1,314 @@
+//telemetry history, every sample of the current session or replay in one place
+//the plots, the replay, and the smoothing all read from here instead of each keeping their own buffer
+//
+//samples are indexed by a timeline in milliseconds built from the device time field, the device clock restarting
+//when the board is power cycled is treated as no time passing so the timeline never goes backwards
+//the history is bounded, once it holds max_samples the oldest samples are dropped
+
+use bevy::prelude::*;
+use crate::ArduinoData;
+use crate::euler::rocket_attitude;
+
+const DEFAULT_MAX_SAMPLES: usize = 500_000; //a bit over 80 minutes at the arduino's 100 Hz
+const BUILT_IN_CHANNELS: [&str; 7] = ["x", "y", "z", "w", "tilt", "heading", "roll"];
+
+//a single sample and where it sits on the timeline
+pub struct HistorySample {
+    pub timeline_ms: f64, //milliseconds since the first sample of the session
+    pub host_time_us: u64, //when the sample was received, microseconds since the session started, zero if not known
+    pub quat: Quat, //orientation in the world frame, normalized
+    pub data: ArduinoData, //the sample as it was received
+}
+
+impl HistorySample {
+    //value of the named channel for this sample, none if the sample does not have it
+    //tilt, heading and roll are the rocket attitude of the world frame orientation, in degrees
+    pub fn channel(&self, name: &str) -> Option<f64> {
+        match name {
+            "x" => Some(self.data.x as f64),
+            "y" => Some(self.data.y as f64),
+            "z" => Some(self.data.z as f64),
+            "w" => Some(self.data.w as f64),
+            "tilt" => Some(rocket_attitude(self.quat).tilt.to_degrees() as f64),
+            "heading" => Some(rocket_attitude(self.quat).heading.to_degrees() as f64),
+            "roll" => Some(rocket_attitude(self.quat).roll.to_degrees() as f64),
+            _ => self.data.extra.get(name).and_then(|value| value.as_f64()),
+        }
+    }
+}
+
+//resource that holds the samples of the current session or replay in timeline order
+#[derive(Resource)]
+pub struct TelemetryHistory {
+    samples: Vec<HistorySample>,
+    max_samples: usize,
+    extra_channels: Vec<String>, //numeric extra fields seen this session, in the order they first showed up
+    dropped_samples: u64, //samples dropped from the front to stay under max_samples
+}
+
+impl Default for TelemetryHistory {
+    fn default() -> Self {
+        Self {
+            samples: vec![],
+            max_samples: DEFAULT_MAX_SAMPLES,
+            extra_channels: vec![],
+            dropped_samples: 0,
+        }
+    }
+}
+
+impl TelemetryHistory {
+    //forgets every sample, used when a new session or replay starts
+    pub fn reset(&mut self) {
+        self.samples.clear();
+        self.extra_channels.clear();
+        self.dropped_samples = 0;
+    }
+
+    //adds a sample to the end of the timeline and returns where it was put, quat is the sample's orientation in the world frame
+    pub fn push(&mut self, data: ArduinoData, host_time_us: u64, quat: Quat) -> f64 {
+        let timeline_ms = match self.samples.last() {
+            Some(previous) => previous.timeline_ms + data.time.saturating_sub(previous.data.time) as f64,
+            None => 0.0,
+        };
+        for (name, value) in &data.extra {
+            if value.is_number() && !self.extra_channels.contains(name) {
+                self.extra_channels.push(name.clone());
+            }
+        }
+        self.samples.push(HistorySample {
+            timeline_ms,
+            host_time_us,
+            quat: quat.normalize(),
+            data,
+        });
+        //drop a tenth at a time so the samples do not have to be shifted on every push
+        if self.samples.len() > self.max_samples {
+            let excess = self.samples.len() - self.max_samples + self.max_samples / 10;
+            self.samples.drain(..excess);
+            self.dropped_samples += excess as u64;
+        }
+        timeline_ms
+    }
+
+    //works out the world frame orientation of every sample again, used when the way samples are turned into the world frame changes
+    pub fn remap(&mut self, to_world: impl Fn(&ArduinoData) -> Quat) {
+        for sample in &mut self.samples {
+            sample.quat = to_world(&sample.data).normalize();
+        }
+    }
+
+    //number of samples in the history
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    //true if no samples have been added since the last reset
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    //samples dropped from the front because the history was full
+    pub fn dropped_samples(&self) -> u64 {
+        self.dropped_samples
+    }
+
+    //the first sample still in the history
+    pub fn first(&self) -> Option<&HistorySample> {
+        self.samples.first()
+    }
+
+    //the newest sample
+    pub fn latest(&self) -> Option<&HistorySample> {
+        self.samples.last()
+    }
+
+    //the newest count samples, oldest first
+    pub fn latest_n(&self, count: usize) -> &[HistorySample] {
+        &self.samples[self.samples.len().saturating_sub(count)..]
+    }
+
+    //samples per second over the newest count samples, from the receive times if they are known and the device times if not
+    pub fn sample_rate(&self, count: usize) -> Option<f64> {
+        let samples = self.latest_n(count);
+        let (first, last) = (samples.first()?, samples.last()?);
+        let span_ms = if last.host_time_us > first.host_time_us {
+            (last.host_time_us - first.host_time_us) as f64 / 1000.0
+        } else {
+            last.timeline_ms - first.timeline_ms
+        };
+        (span_ms > 0.0).then(|| (samples.len() - 1) as f64 * 1000.0 / span_ms)
+    }
+
+    //every sample from start_ms to end_ms on the timeline, both ends included
+    pub fn range(&self, start_ms: f64, end_ms: f64) -> &[HistorySample] {
+        let first = self.samples.partition_point(|sample| sample.timeline_ms < start_ms);
+        let end = self.samples.partition_point(|sample| sample.timeline_ms <= end_ms);
+        &self.samples[first..end.max(first)]
+    }
+
+    //the newest sample at or before time_ms on the timeline, the first sample if time_ms is before it
+    pub fn at_or_before(&self, time_ms: f64) -> Option<&HistorySample> {
+        let index = self.samples.partition_point(|sample| sample.timeline_ms <= time_ms);
+        self.samples.get(index.saturating_sub(1))
+    }
+
+    //the sample closest to time_ms on the timeline
+    pub fn nearest(&self, time_ms: f64) -> Option<&HistorySample> {
+        let index = self.samples.partition_point(|sample| sample.timeline_ms < time_ms);
+        let after = self.samples.get(index);
+        let before = index.checked_sub(1).and_then(|before| self.samples.get(before));
+        match (before, after) {
+            (Some(before), Some(after)) if time_ms - before.timeline_ms <= after.timeline_ms - time_ms => Some(before),
+            (_, Some(after)) => Some(after),
+            (before, None) => before,
+        }
+    }
+
+    //names of every channel that can be plotted, the built in ones first then the extra fields
+    pub fn channel_names(&self) -> Vec<String> {
+        BUILT_IN_CHANNELS.iter().map(|name| name.to_string()).chain(self.extra_channels.iter().cloned()).collect()
+    }
+
+    //points of the named channel from start_ms to end_ms, timeline seconds and value, samples without the channel are skipped
+    //if the range holds more than max_points samples only every nth one is used, so a zoomed out plot stays cheap to build
+    pub fn channel(&self, name: &str, start_ms: f64, end_ms: f64, max_points: usize) -> Vec<[f64; 2]> {
+        let samples = self.range(start_ms, end_ms);
+        let step = samples.len().div_ceil(max_points.max(1)).max(1);
+        samples
+            .iter()
+            .step_by(step)
+            .filter_map(|sample| sample.channel(name).map(|value| [sample.timeline_ms / 1000.0, value]))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //a sample at the given device time, with an altitude field if one is given
+    fn data(time: u32, altitude: Option<f64>) -> ArduinoData {
+        let mut extra = serde_json::Map::new();
+        if let Some(altitude) = altitude {
+            extra.insert("altitude".to_string(), serde_json::Value::from(altitude));
+        }
+        ArduinoData {
+            x: time as f32,
+            y: 0.0,
+            z: 0.0,
+            w: 1.0,
+            time,
+            extra,
+        }
+    }
+
+    //a history holding samples at the given device times
+    fn history(times: impl IntoIterator<Item = u32>) -> TelemetryHistory {
+        let mut history = TelemetryHistory::default();
+        for time in times {
+            history.push(data(time, None), 0, Quat::IDENTITY);
+        }
+        history
+    }
+
+    fn times(samples: &[HistorySample]) -> Vec<f64> {
+        samples.iter().map(|sample| sample.timeline_ms).collect()
+    }
+
+    #[test]
+    fn device_clock_restart_does_not_move_the_timeline_back() {
+        let mut history = TelemetryHistory::default();
+        let timeline: Vec<f64> = [1_000, 1_010, 1_030, 5, 25]
+            .map(|time| history.push(data(time, None), 0, Quat::IDENTITY))
+            .to_vec();
+        //the restart counts as no time passing, after it the timeline carries on from the new device times
+        assert_eq!(timeline, vec![0.0, 10.0, 30.0, 30.0, 50.0]);
+    }
+
+    #[test]
+    fn full_history_drops_a_tenth_from_the_front() {
+        let mut history = TelemetryHistory {
+            max_samples: 10,
+            ..default()
+        };
+        for index in 0..10 {
+            history.push(data(index * 10, None), 0, Quat::IDENTITY);
+        }
+        assert_eq!(history.len(), 10);
+        assert_eq!(history.dropped_samples(), 0);
+        //one over the limit drops the extra sample and a tenth of the limit
+        history.push(data(100, None), 0, Quat::IDENTITY);
+        assert_eq!(history.len(), 9);
+        assert_eq!(history.dropped_samples(), 2);
+        assert_eq!(history.first().unwrap().data.time, 20);
+        assert_eq!(history.latest().unwrap().timeline_ms, 100.0);
+        history.reset();
+        assert!(history.is_empty());
+        assert_eq!(history.dropped_samples(), 0);
+    }
+
+    #[test]
+    fn range_includes_both_ends() {
+        let history = history([0, 10, 20, 30, 40]);
+        assert_eq!(times(history.range(10.0, 30.0)), vec![10.0, 20.0, 30.0]);
+        assert_eq!(times(history.range(10.5, 29.5)), vec![20.0]);
+        assert_eq!(times(history.range(20.0, 20.0)), vec![20.0]);
+        assert!(history.range(41.0, 50.0).is_empty());
+        assert!(history.range(30.0, 10.0).is_empty());
+    }
+
+    #[test]
+    fn at_or_before_falls_back_to_the_first_sample() {
+        let history = history([0, 10, 20]);
+        assert_eq!(history.at_or_before(-5.0).unwrap().timeline_ms, 0.0);
+        assert_eq!(history.at_or_before(10.0).unwrap().timeline_ms, 10.0);
+        assert_eq!(history.at_or_before(19.9).unwrap().timeline_ms, 10.0);
+        assert_eq!(history.at_or_before(100.0).unwrap().timeline_ms, 20.0);
+        assert!(TelemetryHistory::default().at_or_before(0.0).is_none());
+    }
+
+    #[test]
+    fn nearest_picks_the_earlier_sample_on_a_tie() {
+        let history = history([0, 10, 30]);
+        assert_eq!(history.nearest(5.0).unwrap().timeline_ms, 0.0);
+        assert_eq!(history.nearest(5.1).unwrap().timeline_ms, 10.0);
+        assert_eq!(history.nearest(20.0).unwrap().timeline_ms, 10.0);
+        assert_eq!(history.nearest(-1.0).unwrap().timeline_ms, 0.0);
+        assert_eq!(history.nearest(100.0).unwrap().timeline_ms, 30.0);
+        assert!(TelemetryHistory::default().nearest(0.0).is_none());
+    }
+
+    #[test]
+    fn latest_n_with_fewer_samples_than_asked_for() {
+        let history = history([0, 10, 20]);
+        assert_eq!(times(history.latest_n(10)), vec![0.0, 10.0, 20.0]);
+        assert_eq!(times(history.latest_n(2)), vec![10.0, 20.0]);
+        assert!(history.latest_n(0).is_empty());
+        assert!(TelemetryHistory::default().latest_n(5).is_empty());
+    }
+
+    #[test]
+    fn channel_is_thinned_to_at_most_max_points() {
+        let history = history((0..100).map(|index| index * 10));
+        let all = history.channel("x", 0.0, f64::MAX, 1_000);
+        assert_eq!(all.len(), 100);
+        let thinned = history.channel("x", 0.0, f64::MAX, 30);
+        //every fourth sample, starting with the first
+        assert_eq!(thinned.len(), 25);
+        assert_eq!(thinned[0], [0.0, 0.0]);
+        assert_eq!(thinned[1], [0.04, 40.0]);
+        assert_eq!(history.channel("x", 0.0, f64::MAX, 0).len(), 1);
+        assert_eq!(history.channel("x", 100.0, 190.0, 5).len(), 5);
+    }
+
+    #[test]
+    fn channel_skips_samples_without_the_field() {
+        let mut history = TelemetryHistory::default();
+        history.push(data(0, Some(1.5)), 0, Quat::IDENTITY);
+        history.push(data(10, None), 0, Quat::IDENTITY);
+        history.push(data(20, Some(2.5)), 0, Quat::IDENTITY);
+        assert_eq!(history.channel("altitude", 0.0, 20.0, 100), vec![[0.0, 1.5], [0.02, 2.5]]);
+        assert_eq!(history.channel_names().last().map(String::as_str), Some("altitude"));
+    }
+}
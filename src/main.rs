@@ -1,9 +1,49 @@
-use serialport5::{self, SerialPortBuilder, SerialPort};
-use std::io::{BufRead, BufReader, Read};
+use serialport5::{self, SerialPortBuilder};
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
 use serde::{Deserialize, Serialize};
+use bevy::asset::UnapprovedPathMode;
 use bevy::prelude::*;
 use bevy_egui::{ EguiContexts, EguiPlugin, EguiPrimaryContextPass, EguiStartupSet, egui};
 
+mod export;
+mod calibration;
+mod camera;
+mod euler;
+mod frame;
+mod framer;
+mod history;
+mod model;
+mod obj;
+mod plots;
+mod raw_capture;
+mod replay;
+mod scene_aids;
+mod session_log;
+mod smoothing;
+mod source;
+use calibration::{Calibration, ProfileEditor, WorldTransform, ui_system_calibration};
+use camera::{CameraBookmarks, CameraSettings, EguiInputCapture, OrbitCamera, ui_system_camera, ui_system_input_capture, update_orbit_camera};
+use euler::{EulerSettings, show_euler_readout};
+use export::{CsvExport, ui_system_export};
+use frame::{FrameMapping, remap_history, ui_system_frame_mapping};
+use framer::LineFramer;
+use history::TelemetryHistory;
+use model::{ModelPicker, RocketModel, load_rocket_model, receive_dropped_model, ui_system_model};
+use obj::ObjLoader;
+use plots::{TelemetryPlots, ui_system_plots};
+use raw_capture::{RawCaptureStatus, RawCaptureWriter};
+use replay::{ReplaySession, advance_replay, setup_replay, teardown_replay, ui_system_replay};
+use scene_aids::{SceneAids, draw_scene_aids, ui_system_scene_aids};
+use session_log::{LogRecord, SessionHeader, SessionLog, wall_time_ms};
+use smoothing::{OrientationSmoothing, show_smoothing_settings};
+use source::{MotionProfile, RawReplaySource, SerialSource, SimulatorSettings, SimulatorSource, SourceRead, TcpSource, TelemetrySource, UdpSource};
+
 //constants
 const DEFAULT_BAUD_RATE: u32 = 9_600;
 const SUPPORTED_BAUD_RATES: [u32; 13] = [
@@ -21,26 +61,79 @@ const SUPPORTED_BAUD_RATES: [u32; 13] = [
     74_880,
     115_200,
 ]; //list of baud rates the user can choose from
-const ROCKET_MODEL_PATH: &str = "RocketLowPoly.glb";
+const ASSETS_DIRECTORY: &str = "Assets"; //folder bevy loads assets from, relative to the working directory
+const ROCKET_MODEL_PATH: &str = "RocketLowPoly.glb"; //model shown until another is picked, relative to the assets folder
+const MAX_LINE_LENGTH: usize = 512; //longest line the reader thread will accept, anything longer is treated as garbage
+const RECONNECT_POLL_INTERVAL: Duration = Duration::from_millis(500); //how often the reader thread tries to reconnect a broken source
+const DETAILS_INTERVAL: Duration = Duration::from_millis(500); //how often the reader thread sends the source's status details to the ui
+const DEFAULT_UDP_BIND_ADDRESS: &str = "0.0.0.0"; //listen on every network interface by default
+const DEFAULT_UDP_PORT: u16 = 5005;
+const DEFAULT_TCP_ADDRESS: &str = "localhost:2000"; //where a ser2net bridge usually listens
+const SAMPLE_RATE_WINDOW: usize = 100; //how many of the newest samples the sample rate in the monitor window is worked out from
 
 fn main() {
     App::new()
-        .add_plugins(DefaultPlugins)
+        .add_plugins(DefaultPlugins.set(AssetPlugin {
+            file_path: ASSETS_DIRECTORY.to_string(),
+            unapproved_path_mode: UnapprovedPathMode::Allow, //models can be loaded from anywhere, not just the assets folder
+            ..default()
+        }))
         .add_plugins(EguiPlugin::default())
+        .init_asset_loader::<ObjLoader>() //load wavefront obj models as scenes on the asset server's background tasks
         .init_state::<AppState>() //initialize app state to idle
         .add_systems(PreStartup, setup_scene.before(EguiStartupSet::InitContexts)) //setup the 3d scene before egui contexts to avoid errors
         .add_systems(Startup, setup,)//set up serial port list and selection resources
-        .add_systems(OnEnter(AppState::Monitoring), setup_serial_monitor) //when monitoring state is entered, set up the serial monitor
-        .add_systems(Update, (
-            read_line,
-            update_rocket_orientation
-        ).run_if(in_state(AppState::Monitoring))) //read data from serial port and update rocket model every frame
+        .add_systems(OnEnter(AppState::Monitoring), (
+            open_selected_source,
+            setup_serial_monitor,
+        ).chain()) //when monitoring state is entered, open the selected source unless one was already provided, then set up the serial monitor
+        .add_systems(OnExit(AppState::Monitoring), teardown_serial_monitor) //when monitoring state is left, stop the reader thread and clear the data
+        .add_systems(OnEnter(AppState::Replay), setup_replay) //when replay state is entered, load the selected recording
+        .add_systems(OnExit(AppState::Replay), teardown_replay) //when replay state is left, clear the recording and the data
+        .add_systems(Update, read_line.run_if(in_state(AppState::Monitoring).and(resource_exists::<SerialMonitorTools>))) //drain data sent by the reader thread every frame, skipped if the port failed to open
+        .add_systems(Update, advance_replay.run_if(in_state(AppState::Replay).and(resource_exists::<ReplaySession>))) //move the replay forward every frame, skipped if the recording failed to load
+        .add_systems(Update, remap_history.after(read_line).after(advance_replay).run_if(resource_changed::<FrameMapping>.or(resource_changed::<Calibration>))) //put the whole history through the frame mapping and calibration again when either is changed
+        .add_systems(Update, update_rocket_orientation.after(read_line).after(advance_replay).after(remap_history).run_if(resource_exists::<CurrentData>)) //update rocket model every frame once the current data is up to date
+        .add_systems(Update, update_orbit_camera.after(update_rocket_orientation)) //orbit, pan and zoom the camera from mouse and keyboard input every frame, after the rocket has moved so chase and observer views keep up
+        .add_systems(Update, receive_dropped_model) //use a model file dropped onto the window as the rocket model
+        .add_systems(Update, load_rocket_model.after(receive_dropped_model).run_if(resource_changed::<RocketModel>)) //swap the rocket's scene when the model is changed, and load the first one at startup
+        .add_systems(Update, draw_scene_aids.after(update_rocket_orientation)) //draw the grid, axes, and tilt cone around the rocket every frame
         .add_systems(EguiPrimaryContextPass, (
             ui_system_main,
         ))//main ui system for serial port selection, baud rate selection, and starting the serial monitor
         .add_systems(EguiPrimaryContextPass, (
-            ui_system_monitor.run_if(in_state(AppState::Monitoring)),
-        ))//ui system to display current telemetry data
+            ui_system_monitor.run_if(in_state(AppState::Monitoring).or(in_state(AppState::Replay)).and(resource_exists::<CurrentData>)),
+        ))//ui system to display current telemetry data, live or replayed
+        .add_systems(EguiPrimaryContextPass, (
+            ui_system_replay.run_if(in_state(AppState::Replay).and(resource_exists::<ReplaySession>)),
+        ))//ui system for the replay timeline and playback controls
+        .add_systems(EguiPrimaryContextPass, (
+            ui_system_plots.run_if(in_state(AppState::Monitoring).or(in_state(AppState::Replay)).and(resource_exists::<CurrentData>)),
+        ))//ui system to plot the telemetry channels, live or replayed
+        .add_systems(EguiPrimaryContextPass, (
+            ui_system_export,
+        ))//ui system to export the selected recording to csv
+        .add_systems(EguiPrimaryContextPass, (
+            ui_system_frame_mapping,
+        ))//ui system to set up how the imu's frame is turned into the world frame
+        .add_systems(EguiPrimaryContextPass, (
+            ui_system_calibration,
+        ))//ui system for tare, mounting offset, and saving them with the frame mapping as a rocket profile
+        .add_systems(EguiPrimaryContextPass, (
+            ui_system_error.run_if(in_state(AppState::Error)),
+        ))//ui system to show what went wrong and let the user retry or go back to idle
+        .add_systems(EguiPrimaryContextPass, (
+            ui_system_camera,
+        ))//ui system for the camera mode, resetting the view, and camera bookmarks
+        .add_systems(EguiPrimaryContextPass, (
+            ui_system_model,
+        ))//ui system to pick the rocket model
+        .add_systems(EguiPrimaryContextPass, (
+            ui_system_scene_aids,
+        ))//ui system to toggle the scene aids and label the world axes
+        .add_systems(EguiPrimaryContextPass, (
+            ui_system_input_capture,
+        ))//ui system to note whether egui is using the mouse and keyboard so the camera ignores them
         .run();
 }
 
@@ -59,6 +152,7 @@ Overview of app:
 - when user selects a port and presses start, the app will start reading from the serial port
 - app will display the 3d model of the rocket and update its orientation based on the quaternion data received from the serial port
 - app will also display all data received as text on the side of the screen for debugging purposes and will write all data to a file with timestamps for later review
+  (session files go in the sessions folder, one json record per received line)
 
 */
 
@@ -69,13 +163,55 @@ enum AppState {
     #[default]
     Idle,
     Monitoring,
+    Replay, //playing back a recorded file instead of reading live data
+    Error, //something went wrong while monitoring or loading a replay, the MonitorError resource says what
+}
+
+//kinds of telemetry sources the user can pick from
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum SourceKind {
+    Serial,
+    Udp,
+    Tcp,
+    Simulator,
+    RawReplay, //raw capture file played back through the framer
 }
 
-//currently selected serial port and baud rate
+impl SourceKind {
+    //every source kind in the order they are shown in the dropdown
+    const ALL: [SourceKind; 5] = [
+        SourceKind::Serial,
+        SourceKind::Udp,
+        SourceKind::Tcp,
+        SourceKind::Simulator,
+        SourceKind::RawReplay,
+    ];
+
+    //name shown in the source dropdown
+    fn label(&self) -> &'static str {
+        match self {
+            SourceKind::Serial => "Serial Port",
+            SourceKind::Udp => "UDP Listener",
+            SourceKind::Tcp => "TCP Client",
+            SourceKind::Simulator => "Simulator",
+            SourceKind::RawReplay => "Raw Capture Replay",
+        }
+    }
+}
+
+//currently selected source and its settings
 #[derive(Resource)]
 struct SerialMonitorSelection {
+    source_kind: SourceKind,
     port_name: String,
     baud_rate: u32,
+    udp_bind_address: String,
+    udp_port: u16,
+    tcp_address: String,
+    simulator: SimulatorSettings,
+    raw_replay_path: String, //raw capture file for the raw capture replay source
+    raw_capture: bool, //write every chunk read from a byte stream source to a raw capture file
+    replay_path: String, //recording to open in the replay state
 }
 
 //list of available serial ports
@@ -85,13 +221,15 @@ struct SerialPortList {
 }
 
 //struct counterpart to raw json received from arduino
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 struct ArduinoData {
     x: f32,
     y: f32,
     z: f32,
     w: f32,
     time: u32,
+    #[serde(flatten)]
+    extra: serde_json::Map<String, serde_json::Value>, //any other fields the board sends, like altitude or battery voltage
 }
 
 //resource struct that holds the most recent data from serial port
@@ -99,12 +237,127 @@ struct ArduinoData {
 struct CurrentData {
     quat: Quat,
     time: u32,
+    timeline_ms: f64, //where the data sits on the telemetry history timeline
+}
+
+//kinds of errors the serial monitor can run into, used for the error resource and the error counters
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+enum MonitorErrorKind {
+    Open, //source or replay file could not be opened
+    Io, //reading from the source failed, usually because the cable was unplugged, the reader thread will try to reconnect
+    Parse, //a line was received but it was not valid json for ArduinoData
+    Framing, //bytes were thrown away by the line framer because they were garbage
 }
 
-//resource struct that holds the serial port and reader for reading from the serial port
+impl MonitorErrorKind {
+    //every error kind in the order they are shown in the ui
+    const ALL: [MonitorErrorKind; 4] = [
+        MonitorErrorKind::Open,
+        MonitorErrorKind::Io,
+        MonitorErrorKind::Parse,
+        MonitorErrorKind::Framing,
+    ];
+
+    //label shown next to the counter for this kind of error
+    fn label(&self) -> &'static str {
+        match self {
+            MonitorErrorKind::Open => "Failed to open",
+            MonitorErrorKind::Io => "Disconnects",
+            MonitorErrorKind::Parse => "Unparsable lines",
+            MonitorErrorKind::Framing => "Dropped bytes",
+        }
+    }
+}
+
+//resource that holds the error that moved the app into the error state
+#[derive(Resource, Debug)]
+struct MonitorError {
+    kind: MonitorErrorKind,
+    message: String,
+    retry_state: AppState, //state the retry button goes back to
+}
+
+//resource that counts how many times each kind of error has happened since the serial monitor was started
+//framing errors are counted in bytes since the framer only knows how many bytes it threw away
+#[derive(Resource, Default, Debug)]
+struct ErrorCounters {
+    counts: HashMap<MonitorErrorKind, u64>,
+    last_parse_error: Option<String>,
+}
+
+impl ErrorCounters {
+    //adds amount to the counter for the given kind of error
+    fn add(&mut self, kind: MonitorErrorKind, amount: u64) {
+        *self.counts.entry(kind).or_insert(0) += amount;
+    }
+
+    //current count for the given kind of error
+    fn get(&self, kind: MonitorErrorKind) -> u64 {
+        self.counts.get(&kind).copied().unwrap_or(0)
+    }
+}
+
+//events the reader thread sends to the bevy world
+enum ReaderEvent {
+    Line(LogRecord), //a line was received, holds the raw line, when it was received, and the parsed data or parse error
+    DroppedBytes(u64), //the framer threw away this many bytes of garbage
+    Disconnected(String), //reading from the source failed, the thread is now trying to reconnect it
+    Reconnected(String), //the source is working again, holds its new description
+    Failed(String), //the source broke and can not reconnect, the thread has stopped
+    Details(Vec<String>), //the latest status details from the source
+}
+
+//resource that holds the latest status details from the reader thread's source, shown under the connection status
+#[derive(Resource, Default, Debug)]
+struct SourceDetails {
+    lines: Vec<String>,
+}
+
+//resource that holds whether the reader thread's source is currently working
+#[derive(Resource, Debug)]
+enum ConnectionStatus {
+    Connected(String), //holds the description of the source
+    Reconnecting(String), //holds the error that broke the source
+}
+
+//resource that holds the telemetry source the reader thread takes ownership of when monitoring starts
+//anything can put a source in here before the monitoring state is entered to use it instead of the selected serial port
+//the source is wrapped in a mutex since resources have to be sync
+#[derive(Resource, Default)]
+struct TelemetrySourceSlot(Mutex<Option<Box<dyn TelemetrySource>>>);
+
+impl TelemetrySourceSlot {
+    //takes the source out of the slot, leaving it empty
+    fn take(&mut self) -> Option<Box<dyn TelemetrySource>> {
+        self.0.get_mut().ok().and_then(|source| source.take())
+    }
+
+    //puts a source in the slot, replacing any source that was already there
+    fn set(&mut self, source: Box<dyn TelemetrySource>) {
+        if let Ok(slot) = self.0.get_mut() {
+            *slot = Some(source);
+        }
+    }
+
+    //true if a source is waiting in the slot
+    fn is_filled(&mut self) -> bool {
+        self.0.get_mut().is_ok_and(|source| source.is_some())
+    }
+}
+
+//resource struct that holds the receiving end of the reader thread's channel and the flag used to stop the thread
+//the receiver is wrapped in a mutex since resources have to be sync
 #[derive(Resource)]
 struct SerialMonitorTools {
-    port: SerialPort,
+    receiver: Mutex<Receiver<ReaderEvent>>,
+    running: Arc<AtomicBool>,
+}
+
+//tell the reader thread to stop once the tools are dropped, the thread notices on its next read
+impl Drop for SerialMonitorTools {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+    }
 }
 
 //marker component for rocket model
@@ -119,16 +372,20 @@ struct Rocket;
 fn setup(
     mut commands: Commands,
 ) {
-    //get list of serial ports and a list of their names
-    let ports = serialport5::available_ports().unwrap();
-    let mut port_names = vec![];
-    for port in ports {
-        port_names.push(port.port_name);
-    }
+    //get list of serial port names
+    let port_names = list_port_names();
 
     let mut selection = SerialMonitorSelection {
+        source_kind: SourceKind::Serial,
         port_name: String::new(),
         baud_rate: DEFAULT_BAUD_RATE, //default baud rate, can be changed
+        udp_bind_address: DEFAULT_UDP_BIND_ADDRESS.to_string(),
+        udp_port: DEFAULT_UDP_PORT,
+        tcp_address: DEFAULT_TCP_ADDRESS.to_string(),
+        simulator: SimulatorSettings::default(),
+        raw_replay_path: String::new(),
+        raw_capture: false,
+        replay_path: String::new(),
     };
 
     match port_names.len() {
@@ -141,23 +398,39 @@ fn setup(
         ports: port_names,
     });
     commands.insert_resource(selection);
+    commands.insert_resource(ErrorCounters::default());
+    commands.insert_resource(TelemetrySourceSlot::default());
+    commands.insert_resource(CsvExport::default());
+    commands.insert_resource(OrientationSmoothing::default());
+    commands.insert_resource(TelemetryPlots::default());
+    commands.insert_resource(TelemetryHistory::default());
+    commands.insert_resource(EulerSettings::default());
+    commands.insert_resource(FrameMapping::default());
+    commands.insert_resource(Calibration::default());
+    commands.insert_resource(ProfileEditor::default());
+    commands.insert_resource(EguiInputCapture::default());
+    commands.insert_resource(CameraSettings::default());
+    commands.insert_resource(CameraBookmarks::default());
+    commands.insert_resource(SceneAids::default());
+    commands.insert_resource(RocketModel::default());
+    commands.insert_resource(ModelPicker::default());
 }
 
 //scene setup system, will run before egui contexts are set up to avoid any errors
-//sets up the 3d scene with a camera, light, and rocket model
+//sets up the 3d scene with a camera, light, and the rocket
 fn setup_scene(
     mut commands: Commands,
-    asset_server: Res<AssetServer>,
 ) {
-    //import 3d model of rocket and add it to the scene with a Rocket component included for querying later
 
     //default camera distance from world origin
     let camera_distance = 1.0;
 
-    //create camera at about (5, 5, 5) looking at the origin with up being the Y axis
+    //create camera at about (5, 5, 5) looking at the origin with up being the Y axis, orbiting around the origin
+    let orbit_camera = OrbitCamera::new(Vec3::splat(camera_distance), Vec3::ZERO);
     commands.spawn((
         Camera3d::default(),
-        Transform::from_xyz(camera_distance, camera_distance, camera_distance).looking_at(Vec3::ZERO, Vec3::Y),
+        orbit_camera.transform(),
+        orbit_camera,
     ));
 
     //Create directional light to illuminate the scene
@@ -170,91 +443,315 @@ fn setup_scene(
         Transform::from_xyz(camera_distance, camera_distance, camera_distance,).looking_at(Vec3::ZERO, Vec3::Y),
     ));
 
-    //Create rocket model and add the rocket component for updating
+    //Create the rocket and add the rocket component for updating, its model is loaded by load_rocket_model
     commands.spawn((
-        SceneRoot(asset_server.load(
-            GltfAssetLabel::Scene(0).from_asset(ROCKET_MODEL_PATH),
-        )),
         Transform::from_xyz(0.0, 0.0, 0.0),
+        Visibility::default(),
         Rocket,
     ));
 }
 
-//serial monitor setup system, will run when app state switches to monitoring
-//sets up the serial monitor and port reader
-fn setup_serial_monitor(
+//source setup system, will run when app state switches to monitoring before the serial monitor is set up
+//opens the source that is currently selected and puts it in the source slot, unless a source is already waiting there
+//if the source can not be opened the app is moved to the error state instead
+fn open_selected_source(
     mut commands: Commands,
     selected_port: Res<SerialMonitorSelection>,
+    mut source_slot: ResMut<TelemetrySourceSlot>,
+    mut error_counters: ResMut<ErrorCounters>,
+    mut app_state: ResMut<NextState<AppState>>,
 ) {
-    //start port with the name and baud rate that is currently selected in the SerialMonitorSelection resource
-    let port = SerialPortBuilder::new()
-        .baud_rate(selected_port.baud_rate)
-        .open(&selected_port.port_name)
-        .unwrap();
+    if source_slot.is_filled() {
+        return;
+    }
+    //open the source with the settings that are currently selected in the SerialMonitorSelection resource
+    let source: io::Result<Box<dyn TelemetrySource>> = match selected_port.source_kind {
+        SourceKind::Serial => SerialSource::open(&selected_port.port_name, selected_port.baud_rate).map(|source| Box::new(source) as Box<dyn TelemetrySource>),
+        SourceKind::Udp => UdpSource::bind(&selected_port.udp_bind_address, selected_port.udp_port).map(|source| Box::new(source) as Box<dyn TelemetrySource>),
+        SourceKind::Tcp => Ok(Box::new(TcpSource::new(&selected_port.tcp_address))),
+        SourceKind::Simulator => SimulatorSource::new(selected_port.simulator.clone()).map(|source| Box::new(source) as Box<dyn TelemetrySource>),
+        SourceKind::RawReplay => RawReplaySource::open(&selected_port.raw_replay_path).map(|source| Box::new(source) as Box<dyn TelemetrySource>),
+    };
+    match source {
+        Ok(source) => source_slot.set(source),
+        Err(e) => {
+            error_counters.add(MonitorErrorKind::Open, 1);
+            commands.insert_resource(MonitorError {
+                kind: MonitorErrorKind::Open,
+                message: e.to_string(),
+                retry_state: AppState::Monitoring,
+            });
+            app_state.set(AppState::Error);
+        }
+    }
+}
+
+//serial monitor setup system, will run when app state switches to monitoring after the source has been opened
+//starts the reader thread with the source from the source slot, the thread sends every parsed line back through a channel
+fn setup_serial_monitor(
+    mut commands: Commands,
+    selection: Res<SerialMonitorSelection>,
+    mut source_slot: ResMut<TelemetrySourceSlot>,
+    mut history: ResMut<TelemetryHistory>,
+) {
+    //no source means it failed to open and the app is already on its way to the error state
+    let Some(source) = source_slot.take() else {
+        return;
+    };
+
+    //start the reader thread, it owns the source from here on
+    //the session lasts until monitoring is stopped, reconnects keep appending to the same session file
+    let (sender, receiver) = mpsc::channel();
+    let running = Arc::new(AtomicBool::new(true));
+    let thread_running = running.clone();
+    let session_start = Instant::now();
+    history.reset(); //the history holds the whole session
+    commands.insert_resource(if source.is_connected() {
+        ConnectionStatus::Connected(source.describe())
+    } else {
+        ConnectionStatus::Reconnecting(format!("Connecting to {}", source.describe()))
+    });
+    commands.insert_resource(SourceDetails {
+        lines: source.details(),
+    });
+    let (port, baud_rate) = match selection.source_kind {
+        SourceKind::Serial => (Some(selection.port_name.clone()), Some(selection.baud_rate)),
+        _ => (None, None),
+    };
+    let header = SessionHeader::new(source.describe(), port, baud_rate);
+    //only the byte stream sources have chunks to capture, replaying a raw capture would only capture the same bytes again
+    let raw_capture = if selection.raw_capture && matches!(selection.source_kind, SourceKind::Serial | SourceKind::Tcp) {
+        let (status, raw_capture) = RawCaptureWriter::start(header.start_wall_ms);
+        commands.insert_resource(status);
+        raw_capture
+    } else {
+        None
+    };
+    commands.insert_resource(SessionLog::start(header));
+    thread::spawn(move || reader_thread(source, session_start, raw_capture, sender, thread_running));
 
     //insert serial monitor tools resource
     commands.insert_resource(SerialMonitorTools {
-        port,
+        receiver: Mutex::new(receiver),
+        running,
     });
 
     //insert current data resource with initial values
     let current_data = CurrentData {
         quat: Quat::IDENTITY,
         time: 0,
+        timeline_ms: 0.0,
     };
     commands.insert_resource(current_data);
 }
 
+//serial monitor teardown system, will run when app state switches away from monitoring
+//removes the serial monitor tools and current data, dropping the tools tells the reader thread to stop and close the port
+fn teardown_serial_monitor(
+    mut commands: Commands,
+) {
+    commands.remove_resource::<SerialMonitorTools>();
+    commands.remove_resource::<CurrentData>();
+    commands.remove_resource::<ConnectionStatus>();
+    commands.remove_resource::<SourceDetails>();
+    commands.remove_resource::<SessionLog>(); //dropping the log waits for the writer thread to finish the file
+    commands.remove_resource::<RawCaptureStatus>(); //the reader thread closes the capture file itself when it stops
+}
+
 
 // UPDATE SYSTEMS
 
-//data update system, runs every fram while in the monitoring state
-//reads a line from the serial port, parses it into a json, and updates the current data resource with parsed data
+//data update system, runs every frame while in the monitoring state
+//drains every event the reader thread has sent since the last frame, updates the current data resource and the history with new samples and counts errors
+//every received line is written to the session log, including the ones that could not be parsed
+//if the source broke for good the app is moved to the error state
+#[allow(clippy::too_many_arguments)]
 fn read_line(
+    mut commands: Commands,
     mut serial_tools: ResMut<SerialMonitorTools>,
     mut current_data: ResMut<CurrentData>,
+    frame_mapping: Res<FrameMapping>,
+    calibration: Res<Calibration>,
+    mut history: ResMut<TelemetryHistory>,
+    mut session_log: ResMut<SessionLog>,
+    mut error_counters: ResMut<ErrorCounters>,
+    mut connection_status: ResMut<ConnectionStatus>,
+    mut source_details: ResMut<SourceDetails>,
+    mut app_state: ResMut<NextState<AppState>>,
 ) {
-
-    let mut buffer = [0; 128]; //128 byte buffer that the reader will fill every frame
-    //optimally this entire thing would be its own thread started when monitoring state is entered but i dont know enough about rust multithreading for that so we will still lose some data since the frame time is about 60hz or 7-8ish ms
-    match serial_tools.port.read(&mut buffer) {
-        Ok(bytes_read) => {
-            let data = String::from_utf8_lossy(&buffer[..bytes_read]);
-            //lets get the last line and and serde it and dump the rest into the log file
-
-            //pretend like we wrote that to a log file and continue
-            let last_newline = data.rfind('\n').unwrap_or(0);
-            let second_to_last_newline = data[..last_newline].rfind('\n').unwrap_or(std::usize::MAX);
-            //sometimes we dont happen to catch enough data to get a full line and in that case we just wait until the next frame
-            if last_newline == 0 || second_to_last_newline == std::usize::MAX {
+    //the mutex is only ever used from this system so it can not be poisoned, get_mut skips the locking
+    let Ok(receiver) = serial_tools.receiver.get_mut() else {
+        return;
+    };
+    let transform = WorldTransform::new(&frame_mapping, &calibration);
+    for event in receiver.try_iter() {
+        match event {
+            ReaderEvent::Line(record) => {
+                match (&record.data, &record.parse_error) {
+                    (Some(data_line), _) => {
+                        //turn the sample into the world frame and take out the calibration before anything else uses it
+                        current_data.quat = transform.apply(data_line);
+                        current_data.time = data_line.time;
+                        current_data.timeline_ms = history.push(data_line.clone(), record.host_time_us, current_data.quat);
+                    }
+                    (None, parse_error) => {
+                        error_counters.add(MonitorErrorKind::Parse, 1);
+                        error_counters.last_parse_error = Some(format!("{:?}: {}", record.raw, parse_error.as_deref().unwrap_or("unknown error")));
+                    }
+                }
+                session_log.write(record);
+            }
+            ReaderEvent::DroppedBytes(bytes) => error_counters.add(MonitorErrorKind::Framing, bytes),
+            ReaderEvent::Disconnected(message) => {
+                error_counters.add(MonitorErrorKind::Io, 1);
+                *connection_status = ConnectionStatus::Reconnecting(message);
+            }
+            ReaderEvent::Reconnected(description) => *connection_status = ConnectionStatus::Connected(description),
+            ReaderEvent::Details(lines) => source_details.lines = lines,
+            ReaderEvent::Failed(message) => {
+                commands.insert_resource(MonitorError {
+                    kind: MonitorErrorKind::Io,
+                    message,
+                    retry_state: AppState::Monitoring,
+                });
+                //the reader thread has already stopped, the tools get cleaned up when the monitoring state is left
+                app_state.set(AppState::Error);
                 return;
             }
-            let data_line: ArduinoData = serde_json::from_str(&data[second_to_last_newline+1..last_newline]).unwrap();
-            current_data.quat = Quat::from_xyzw(data_line.x, data_line.y, data_line.z, data_line.w);
-            current_data.time = data_line.time;
         }
-        Err(ref e) if e.kind() == std::io::ErrorKind::TimedOut => (),
-        Err(e) => panic!("Error reading from serial port: {:?}", e),
     }
 }
 
-//rocket model update system, runs every frame while in the monitoring state after the current data has been updated
-//updates the orientation of the rocket model to match the most recent data received from the serial port
+//rocket model update system, runs every frame while in the monitoring or replay state after the current data has been updated
+//moves the orientation of the rocket model toward the most recent data received from the serial port with the selected smoothing
 fn update_rocket_orientation(
+    time: Res<Time>,
     current_data: Res<CurrentData>,
+    history: Res<TelemetryHistory>,
+    mut smoothing: ResMut<OrientationSmoothing>,
     mut query: Query<&mut Transform, With<Rocket>>,
 ) {
-    //get the most recent quat data
-    let quat = current_data.quat.clone();
-    //set the rocket models orientation to the quat received from the serial port
-    //todo make it smoothly move to each orientation to make it look less choppy
-    //can be done by making the newest orientation the target then slerp between current and target each frame
+    //new session or replay, the samples from the last one have nothing to do with this one
+    if current_data.is_added() {
+        smoothing.reset();
+    }
+    //get the smoothed orientation for this frame
+    let quat = smoothing.update(current_data.quat, current_data.timeline_ms, &history, time.delta_secs());
+    //set the rocket models orientation
     for mut transform in &mut query {
         transform.rotation = quat;
     }
 }
 
 
+// READER THREAD
+
+//reader thread started by setup_serial_monitor, runs until the serial monitor tools are dropped
+//polls the source continuously, splits stream bytes into lines, and sends every parsed line to the bevy world
+//stream bytes are written to the raw capture exactly as they were read before they go through the framer
+//if the source breaks the thread keeps trying to reconnect it, sources that can not reconnect stop the thread
+//nothing in here panics, errors are sent to the bevy world as events instead
+fn reader_thread(
+    mut source: Box<dyn TelemetrySource>,
+    session_start: Instant,
+    mut raw_capture: Option<RawCaptureWriter>,
+    sender: Sender<ReaderEvent>,
+    running: Arc<AtomicBool>,
+) {
+    let mut framer = LineFramer::new(MAX_LINE_LENGTH);
+    let mut connected = source.is_connected(); //sources that connect in the background start out reconnecting
+    let mut was_connected = connected; //false until the source has worked once, its first connect is not a reconnect
+    let mut last_details = Instant::now();
+    while running.load(Ordering::Relaxed) {
+        //keep the ui up to date with the source's status, also while it is reconnecting
+        if last_details.elapsed() >= DETAILS_INTERVAL {
+            last_details = Instant::now();
+            if sender.send(ReaderEvent::Details(source.details())).is_err() {
+                return;
+            }
+        }
+        if !connected {
+            match source.reconnect() {
+                Ok(()) => {
+                    //whatever half line was left from before the disconnect will never be finished
+                    //the capture gets a marker for it so a replay clears the framer at the same place
+                    if was_connected {
+                        if let Some(raw_capture) = &mut raw_capture {
+                            raw_capture.write_disconnect(session_start.elapsed().as_micros() as u64);
+                        }
+                        let dropped_before = framer.dropped_bytes();
+                        framer.clear();
+                        if framer.dropped_bytes() != dropped_before && sender.send(ReaderEvent::DroppedBytes(framer.dropped_bytes() - dropped_before)).is_err() {
+                            return;
+                        }
+                    }
+                    if sender.send(ReaderEvent::Reconnected(source.describe())).is_err() {
+                        return;
+                    }
+                    connected = true;
+                }
+                Err(e) if e.kind() == io::ErrorKind::Unsupported => {
+                    let _ = sender.send(ReaderEvent::Failed(format!("{} stopped and can not reconnect", source.describe())));
+                    return;
+                }
+                Err(_) => thread::sleep(RECONNECT_POLL_INTERVAL),
+            }
+            continue;
+        }
+        let mut events = vec![];
+        let read = source.poll();
+        was_connected |= read.is_ok();
+        match read {
+            Ok(SourceRead::Bytes(bytes)) => {
+                if let Some(raw_capture) = &mut raw_capture {
+                    raw_capture.write_chunk(session_start.elapsed().as_micros() as u64, &bytes);
+                }
+                //the framer keeps any unfinished line around until the rest of it shows up in a later read
+                let dropped_before = framer.dropped_bytes();
+                for line in framer.push(&bytes) {
+                    events.push(receive_line(line, session_start));
+                }
+                if framer.dropped_bytes() != dropped_before {
+                    events.push(ReaderEvent::DroppedBytes(framer.dropped_bytes() - dropped_before));
+                }
+            }
+            Ok(SourceRead::Records(records)) => {
+                for record in records {
+                    events.push(receive_line(record.trim().to_string(), session_start));
+                }
+            }
+            Err(e) => {
+                connected = false;
+                events.push(ReaderEvent::Disconnected(e.to_string()));
+            }
+        }
+        for event in events {
+            //the bevy side is gone so there is nobody left to read from us
+            if sender.send(event).is_err() {
+                return;
+            }
+        }
+    }
+}
+
+//timestamps and parses a single json line into the event the reader thread sends for it
+fn receive_line(line: String, session_start: Instant) -> ReaderEvent {
+    let host_time_us = session_start.elapsed().as_micros() as u64;
+    let wall_time_ms = wall_time_ms();
+    let (data, parse_error) = match serde_json::from_str::<ArduinoData>(&line) {
+        Ok(data_line) => (Some(data_line), None),
+        Err(e) => (None, Some(e.to_string())),
+    };
+    ReaderEvent::Line(LogRecord {
+        host_time_us,
+        wall_time_ms,
+        raw: line,
+        data,
+        parse_error,
+    })
+}
+
+
 // UI SYSTEMS
 
 //main ui system, runs every frame, allows user to select serial port, baud rate, and start the serial monitor
@@ -262,89 +759,296 @@ fn ui_system_main(
     mut contexts: EguiContexts,
     mut serial_port_list: ResMut<SerialPortList>,
     mut selection: ResMut<SerialMonitorSelection>,
+    mut error_counters: ResMut<ErrorCounters>,
     mut app_state: ResMut<NextState<AppState>>,
     current_app_state: Res<State<AppState>>,
 ) -> Result<(), BevyError> {
     let ctx = contexts.ctx_mut()?;
     //create floating window with dropdowns to select serial port and baud rate, and a button to start the serial monitor
+    let monitoring = *current_app_state == AppState::Monitoring;
+    let replaying = *current_app_state == AppState::Replay;
     egui::Window::new("Serial Monitor Options")
         .default_width(200.0)
         .show(ctx, |ui| {
-            //dropdown to select serial port
-            ui.horizontal(|ui| {
-                //check current selections
-                let mut current_port = selection.port_name.clone();
-                let mut current_baud_rate = selection.baud_rate;
-                //serial port selection dropdown
-                ui.label("Serial Port:");
-                egui::ComboBox::from_label("")
-                    .selected_text(current_port.clone())
-                    .show_ui(ui, |ui| {
-                        for port in &serial_port_list.ports {
-                            ui.selectable_value(&mut current_port, port.clone(), port.clone());
+            //source and its settings can only be changed while the serial monitor is stopped
+            ui.add_enabled_ui(!monitoring && !replaying, |ui| {
+                //dropdown to select the kind of source
+                ui.horizontal(|ui| {
+                    ui.label("Source:");
+                    egui::ComboBox::from_id_salt("source_kind")
+                        .selected_text(selection.source_kind.label())
+                        .show_ui(ui, |ui| {
+                            for source_kind in SourceKind::ALL {
+                                ui.selectable_value(&mut selection.source_kind, source_kind, source_kind.label());
+                            }
+                        });
+                });
+                match selection.source_kind {
+                    SourceKind::Serial => {
+                        //dropdown to select serial port
+                        ui.horizontal(|ui| {
+                            //check current selections
+                            let mut current_port = selection.port_name.clone();
+                            let mut current_baud_rate = selection.baud_rate;
+                            //serial port selection dropdown
+                            ui.label("Serial Port:");
+                            egui::ComboBox::from_label("")
+                                .selected_text(current_port.clone())
+                                .show_ui(ui, |ui| {
+                                    for port in &serial_port_list.ports {
+                                        ui.selectable_value(&mut current_port, port.clone(), port.clone());
+                                    }
+                                });
+                            //change selected port if user selected a different one from the dropdown
+                            if current_port != selection.port_name {
+                                println!("Switching selected port");
+                                selection.port_name = current_port;
+                            }
+                            ui.label("at");
+                            //baud rate selection dropdown
+                            egui::ComboBox::from_label("Baud")
+                                .selected_text(selection.baud_rate.to_string())
+                                .show_ui(ui, |ui| {
+                                    for baud_rate in SUPPORTED_BAUD_RATES {
+                                        ui.selectable_value(&mut current_baud_rate, baud_rate, baud_rate.to_string());
+                                    }
+                                });
+                            //change selected baud rate if user selected a different one from the dropdown
+                            if current_baud_rate != selection.baud_rate {
+                                println!("Switching selected baud rate");
+                                selection.baud_rate = current_baud_rate;
+                            }
+                        });
+                    }
+                    SourceKind::Udp => {
+                        //address and port to listen on
+                        ui.horizontal(|ui| {
+                            ui.label("Bind Address:");
+                            ui.text_edit_singleline(&mut selection.udp_bind_address);
+                            ui.label("Port:");
+                            ui.add(egui::DragValue::new(&mut selection.udp_port).range(1..=u16::MAX));
+                        });
+                    }
+                    SourceKind::Tcp => {
+                        //server to connect to
+                        ui.horizontal(|ui| {
+                            ui.label("Address (host:port):");
+                            ui.text_edit_singleline(&mut selection.tcp_address);
+                        });
+                    }
+                    SourceKind::Simulator => {
+                        //motion profile and its settings
+                        let simulator = &mut selection.simulator;
+                        ui.horizontal(|ui| {
+                            ui.label("Profile:");
+                            egui::ComboBox::from_id_salt("simulator_profile")
+                                .selected_text(simulator.profile.label())
+                                .show_ui(ui, |ui| {
+                                    for profile in MotionProfile::ALL {
+                                        ui.selectable_value(&mut simulator.profile, profile, profile.label());
+                                    }
+                                });
+                            ui.label("Rate:");
+                            ui.add(egui::DragValue::new(&mut simulator.rate_hz).range(1.0..=1_000.0).suffix(" Hz"));
+                        });
+                        match simulator.profile {
+                            MotionProfile::Spin => {
+                                ui.horizontal(|ui| {
+                                    ui.label("Axis:");
+                                    ui.add(egui::DragValue::new(&mut simulator.spin_axis.x).speed(0.05).prefix("x "));
+                                    ui.add(egui::DragValue::new(&mut simulator.spin_axis.y).speed(0.05).prefix("y "));
+                                    ui.add(egui::DragValue::new(&mut simulator.spin_axis.z).speed(0.05).prefix("z "));
+                                    ui.label("Rate:");
+                                    ui.add(egui::DragValue::new(&mut simulator.spin_rate).suffix(" °/s"));
+                                });
+                            }
+                            MotionProfile::Coning => {
+                                ui.horizontal(|ui| {
+                                    ui.label("Half Angle:");
+                                    ui.add(egui::DragValue::new(&mut simulator.cone_half_angle).range(0.0..=90.0).suffix("°"));
+                                    ui.label("Rate:");
+                                    ui.add(egui::DragValue::new(&mut simulator.spin_rate).suffix(" °/s"));
+                                });
+                            }
+                            MotionProfile::LaunchAndTumble => (),
                         }
-                    });
-                //change selected port if user selected a different one from the dropdown
-                if current_port != selection.port_name {
-                    println!("Switching selected port");
-                    selection.port_name = current_port;
+                        ui.horizontal(|ui| {
+                            ui.label("Noise:");
+                            ui.add(egui::DragValue::new(&mut simulator.noise).range(0.0..=0.5).speed(0.001));
+                        });
+                    }
+                    SourceKind::RawReplay => {
+                        //capture file to play back
+                        ui.horizontal(|ui| {
+                            ui.label("Capture File:");
+                            ui.text_edit_singleline(&mut selection.raw_replay_path);
+                        });
+                    }
                 }
-                ui.label("at");
-                //baud rate selection dropdown
-                egui::ComboBox::from_label("Baud")
-                    .selected_text(selection.baud_rate.to_string())
-                    .show_ui(ui, |ui| {
-                        for baud_rate in SUPPORTED_BAUD_RATES {
-                            ui.selectable_value(&mut current_baud_rate, baud_rate, baud_rate.to_string());
-                        }
-                    });
-                //change selected baud rate if user selected a different one from the dropdown
-                if current_baud_rate != selection.baud_rate {
-                    println!("Switching selected baud rate");
-                    selection.baud_rate = current_baud_rate;
+                //only sources that read a byte stream have chunks to capture, the serial port and tcp client
+                if matches!(selection.source_kind, SourceKind::Serial | SourceKind::Tcp) {
+                    ui.checkbox(&mut selection.raw_capture, "Capture raw bytes");
                 }
             });
             ui.horizontal(|ui| {
                 //refresh ports button
-                if ui.button("Refresh Ports").clicked() {
+                if ui.add_enabled(!monitoring && !replaying, egui::Button::new("Refresh Ports")).clicked() {
                     //refresh list of ports
-                    let ports = serialport5::available_ports().unwrap();
-                    let mut port_names = vec![];
-                    for port in ports {
-                        port_names.push(port.port_name);
-                    }
-                    serial_port_list.ports = port_names;
+                    serial_port_list.ports = list_port_names();
                 }
-                //start serial monitor button
-                if ui.button("Start Serial Monitor").clicked() {
-                    //only start if a valid port is selected and if the app is not already monitoring
-                    if selection.port_name != "None" && *current_app_state != AppState::Monitoring {
-                        app_state.set(AppState::Monitoring);
-                    } else {
-                        println!("No valid port selected or already monitoring");
+                if monitoring {
+                    //stop serial monitor button, the teardown system takes care of closing the port
+                    if ui.button("Stop Serial Monitor").clicked() {
+                        app_state.set(AppState::Idle);
+                    }
+                } else {
+                    //start serial monitor button
+                    if ui.add_enabled(!replaying, egui::Button::new("Start Serial Monitor")).clicked() {
+                        //only start if a valid port is selected, other sources check their settings when they are opened
+                        if selection.source_kind != SourceKind::Serial || selection.port_name != "None" {
+                            *error_counters = ErrorCounters::default(); //new monitor, new counts
+                            app_state.set(AppState::Monitoring);
+                        } else {
+                            println!("No valid port selected");
+                        }
                     }
                 }
             });
+            ui.separator();
+            //recorded file to replay instead of live data, the replay window takes over once it is open
+            ui.add_enabled_ui(!monitoring && !replaying, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Recording:");
+                    ui.text_edit_singleline(&mut selection.replay_path);
+                    if ui.button("Open Replay").clicked() {
+                        *error_counters = ErrorCounters::default();
+                        app_state.set(AppState::Replay);
+                    }
+                });
+            });
         });
     Ok(())
 }
 
-//data monitor ui system, runs every frame while in the monitoring state, displays the most recent data received from the serial port
+//data monitor ui system, runs every frame while in the monitoring or replay state, displays the most recent data received from the source or the recording
+#[allow(clippy::too_many_arguments)]
 fn ui_system_monitor(
     mut contexts: EguiContexts,
     current_data: Res<CurrentData>,
+    error_counters: Res<ErrorCounters>,
+    connection_status: Option<Res<ConnectionStatus>>,
+    source_details: Option<Res<SourceDetails>>,
+    session_log: Option<Res<SessionLog>>,
+    raw_capture_status: Option<Res<RawCaptureStatus>>,
+    history: Res<TelemetryHistory>,
+    mut euler_settings: ResMut<EulerSettings>,
+    mut smoothing: ResMut<OrientationSmoothing>,
 ) -> Result<(), BevyError> {
     let ctx = contexts.ctx_mut()?;
     //create floating window that displays the most recent data received from the serial port
     egui::Window::new("Serial Monitor Data")
         .default_width(200.0)
         .show(ctx, |ui| {
+            match connection_status.as_deref() {
+                Some(ConnectionStatus::Connected(port_name)) => {
+                    ui.label(format!("Connected to {}", port_name));
+                }
+                Some(ConnectionStatus::Reconnecting(message)) => {
+                    ui.colored_label(egui::Color32::YELLOW, format!("Reconnecting... ({})", message));
+                }
+                None => (),
+            }
+            if let Some(source_details) = &source_details {
+                for line in &source_details.lines {
+                    ui.label(line);
+                }
+            }
+            //the session log and the raw capture, red once writing has stopped
+            let file_statuses = [session_log.as_ref().map(|log| log.status()), raw_capture_status.as_ref().map(|status| &status.0)];
+            for status in file_statuses.into_iter().flatten() {
+                if status.has_failed() {
+                    ui.colored_label(egui::Color32::LIGHT_RED, status.summary());
+                } else {
+                    ui.label(status.summary());
+                }
+            }
+            if let Some(sample_rate) = history.sample_rate(SAMPLE_RATE_WINDOW) {
+                ui.label(format!("Sample rate: {:.1} Hz", sample_rate));
+            }
+            if history.dropped_samples() > 0 {
+                ui.colored_label(egui::Color32::YELLOW, format!("History full, dropped the oldest {} samples", history.dropped_samples()));
+            }
             ui.label(format!("Time: {}", current_data.time));
             ui.label(format!("Quaternion: ({}, {}, {}, {})", current_data.quat.x, current_data.quat.y, current_data.quat.z, current_data.quat.w));
+            show_euler_readout(ui, &mut euler_settings, current_data.quat);
+            show_smoothing_settings(ui, &mut smoothing);
+            ui.separator();
+            show_error_counters(ui, &error_counters);
+        });
+    Ok(())
+}
+
+//error ui system, runs every frame while in the error state
+//shows the error that stopped the serial monitor and lets the user retry or go back to idle
+fn ui_system_error(
+    mut contexts: EguiContexts,
+    monitor_error: Option<Res<MonitorError>>,
+    error_counters: Res<ErrorCounters>,
+    mut app_state: ResMut<NextState<AppState>>,
+) -> Result<(), BevyError> {
+    let ctx = contexts.ctx_mut()?;
+    egui::Window::new("Serial Monitor Error")
+        .default_width(300.0)
+        .show(ctx, |ui| {
+            match &monitor_error {
+                Some(monitor_error) => {
+                    ui.colored_label(egui::Color32::LIGHT_RED, format!("{}: {}", monitor_error.kind.label(), monitor_error.message));
+                }
+                None => {
+                    ui.colored_label(egui::Color32::LIGHT_RED, "Unknown error");
+                }
+            }
+            ui.separator();
+            show_error_counters(ui, &error_counters);
+            ui.horizontal(|ui| {
+                //retry with the same selection, goes through the monitoring or replay setup again
+                if ui.button("Retry").clicked() {
+                    let retry_state = monitor_error.as_ref().map_or(AppState::Monitoring, |monitor_error| monitor_error.retry_state.clone());
+                    app_state.set(retry_state);
+                }
+                if ui.button("Back to Idle").clicked() {
+                    app_state.set(AppState::Idle);
+                }
+            });
         });
     Ok(())
 }
 
+//shows the error counters and the last parse error, used by both the monitor and error windows
+fn show_error_counters(ui: &mut egui::Ui, error_counters: &ErrorCounters) {
+    for kind in MonitorErrorKind::ALL {
+        ui.label(format!("{}: {}", kind.label(), error_counters.get(kind)));
+    }
+    if let Some(last_parse_error) = &error_counters.last_parse_error {
+        ui.label(format!("Last parse error: {}", last_parse_error));
+    }
+}
+
+
+// HELPERS
+
+//gets the names of every serial port currently available
+//returns an empty list if the ports could not be listed instead of stopping the app
+fn list_port_names() -> Vec<String> {
+    match serialport5::available_ports() {
+        Ok(ports) => ports.into_iter().map(|port| port.port_name).collect(),
+        Err(e) => {
+            println!("Could not list serial ports: {}", e);
+            vec![]
+        }
+    }
+}
+
 
 // DEPRACATED SYSTEMS AND STRUCTS
 
@@ -0,0 +1,216 @@
+//rocket model picker, which model file the rocket is drawn with
+//models in the assets folder are listed, any other gltf, glb or obj file can be typed in or dropped onto the window
+//models are loaded in the background by the asset server, gltf models by bevy's loader and obj models by the obj module's
+//swapping the model only replaces the rocket's scene, its transform is left to the telemetry
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use bevy::asset::{AssetPath, LoadState};
+use bevy::prelude::*;
+use bevy::window::FileDragAndDrop;
+use bevy_egui::{EguiContexts, egui};
+use crate::{ASSETS_DIRECTORY, ROCKET_MODEL_PATH, Rocket};
+
+const MODEL_EXTENSIONS: [&str; 3] = ["glb", "gltf", "obj"];
+
+//resource that holds the model file the rocket is drawn with, changing it swaps the model
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct RocketModel {
+    pub path: PathBuf, //relative to the working directory so profiles can be shared with the repo, absolute for models outside it
+}
+
+impl Default for RocketModel {
+    fn default() -> Self {
+        Self {
+            path: Path::new(ASSETS_DIRECTORY).join(ROCKET_MODEL_PATH),
+        }
+    }
+}
+
+//resource that holds the model picker's state for the ui
+#[derive(Resource)]
+pub struct ModelPicker {
+    typed_path: String,
+    available: Vec<PathBuf>, //models in the assets folder, relative to the working directory
+    handle: Handle<Scene>, //scene of the model being shown, to check how loading it went
+    last_error: Option<String>,
+}
+
+impl Default for ModelPicker {
+    fn default() -> Self {
+        Self {
+            typed_path: Path::new(ASSETS_DIRECTORY).join(ROCKET_MODEL_PATH).display().to_string(),
+            available: list_models(),
+            handle: Handle::default(),
+            last_error: None,
+        }
+    }
+}
+
+//the file's extension in lower case, empty if it has none
+fn extension_of(path: &Path) -> String {
+    path.extension().map_or(String::new(), |extension| extension.to_string_lossy().to_ascii_lowercase())
+}
+
+//true if the file has an extension the model picker can load
+fn is_model_file(path: &Path) -> bool {
+    MODEL_EXTENSIONS.contains(&extension_of(path).as_str())
+}
+
+//every model file in the assets folder and the folders inside it
+fn list_models() -> Vec<PathBuf> {
+    let mut models = vec![];
+    let mut folders = vec![PathBuf::from(ASSETS_DIRECTORY)];
+    while let Some(folder) = folders.pop() {
+        let Ok(entries) = fs::read_dir(&folder) else {
+            continue;
+        };
+        for path in entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()) {
+            if path.is_dir() {
+                folders.push(path);
+            } else if is_model_file(&path) {
+                models.push(path);
+            }
+        }
+    }
+    models.sort();
+    models
+}
+
+//path to keep for a model file, relative to the working directory if it is inside it so it means the same on any checkout
+fn model_path_for(file: &Path) -> PathBuf {
+    match (env::current_dir().and_then(fs::canonicalize), fs::canonicalize(file)) {
+        (Ok(working), Ok(file)) => file.strip_prefix(&working).map_or(file.clone(), Path::to_path_buf),
+        _ => file.to_path_buf(),
+    }
+}
+
+//path the asset server loads a model from, it looks up relative paths in the assets folder instead of the working directory
+fn asset_path_for(path: &Path) -> AssetPath<'static> {
+    match path.strip_prefix(ASSETS_DIRECTORY) {
+        Ok(inside_assets) => AssetPath::from(inside_assets.to_path_buf()),
+        Err(_) if path.is_relative() => AssetPath::from(env::current_dir().unwrap_or_default().join(path)),
+        Err(_) => AssetPath::from(path.to_path_buf()),
+    }
+}
+
+//checks a path typed in or dropped before it is used as the model, the error is shown in the model window
+fn check_model_path(path: &Path) -> Result<PathBuf, String> {
+    if !is_model_file(path) {
+        return Err(format!("{} is not a glTF, GLB or OBJ model", path.display()));
+    }
+    //relative paths are looked up from the working directory, then in the assets folder so models there can be typed by name
+    let file = if path.is_file() { path.to_path_buf() } else { Path::new(ASSETS_DIRECTORY).join(path) };
+    if !file.is_file() {
+        return Err(format!("{} does not exist", path.display()));
+    }
+    Ok(model_path_for(&file))
+}
+
+
+// UPDATE SYSTEMS
+
+//model update system, runs when the rocket model is changed and once at startup
+//puts the new model's scene on the rocket, bevy takes the old scene's entities away when the scene root is replaced
+pub fn load_rocket_model(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    model: Res<RocketModel>,
+    mut picker: ResMut<ModelPicker>,
+    query: Query<Entity, With<Rocket>>,
+) {
+    picker.typed_path = model.path.display().to_string();
+    picker.last_error = None;
+    let path = asset_path_for(&model.path);
+    //the obj loader's whole asset is the scene, gltf files hold a list of scenes
+    let handle: Handle<Scene> = if extension_of(&model.path) == "obj" {
+        asset_server.load(path)
+    } else {
+        asset_server.load(GltfAssetLabel::Scene(0).from_asset(path))
+    };
+    for rocket in &query {
+        commands.entity(rocket).insert(SceneRoot(handle.clone()));
+    }
+    picker.handle = handle;
+}
+
+//drop update system, runs every frame
+//a model file dropped onto the window becomes the rocket model
+pub fn receive_dropped_model(
+    mut drops: MessageReader<FileDragAndDrop>,
+    mut model: ResMut<RocketModel>,
+    mut picker: ResMut<ModelPicker>,
+) {
+    for drop in drops.read() {
+        if let FileDragAndDrop::DroppedFile { path_buf, .. } = drop {
+            match check_model_path(path_buf) {
+                Ok(path) => {
+                    model.set_if_neq(RocketModel { path });
+                }
+                Err(e) => picker.last_error = Some(e),
+            }
+        }
+    }
+}
+
+
+// UI SYSTEMS
+
+//model ui system, runs every frame
+//pick one of the models in the assets folder or type in the path to any other, and see whether it loaded
+pub fn ui_system_model(
+    mut contexts: EguiContexts,
+    mut model: ResMut<RocketModel>,
+    mut picker: ResMut<ModelPicker>,
+    asset_server: Res<AssetServer>,
+) -> Result<(), BevyError> {
+    let ctx = contexts.ctx_mut()?;
+    egui::Window::new("Rocket Model")
+        .default_width(250.0)
+        .default_open(false)
+        .show(ctx, |ui| {
+            let picker = &mut *picker;
+            ui.horizontal(|ui| {
+                ui.label("Model:");
+                egui::ComboBox::from_id_salt("rocket_model")
+                    .selected_text(model.path.display().to_string())
+                    .show_ui(ui, |ui| {
+                        for path in &picker.available {
+                            if ui.selectable_label(*path == model.path, path.display().to_string()).clicked() {
+                                model.set_if_neq(RocketModel { path: path.clone() });
+                            }
+                        }
+                    });
+                if ui.button("Refresh").clicked() {
+                    picker.available = list_models();
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label("Path:");
+                ui.text_edit_singleline(&mut picker.typed_path);
+                if ui.button("Load").clicked() {
+                    match check_model_path(Path::new(picker.typed_path.trim())) {
+                        Ok(path) => {
+                            model.set_if_neq(RocketModel { path });
+                        }
+                        Err(e) => picker.last_error = Some(e),
+                    }
+                }
+            });
+            ui.label("Or drop a model file onto the window");
+            match asset_server.get_load_state(&picker.handle) {
+                Some(LoadState::Loading) => {
+                    ui.label("Loading...");
+                }
+                Some(LoadState::Failed(e)) => {
+                    ui.colored_label(egui::Color32::LIGHT_RED, format!("Could not load the model: {}", e));
+                }
+                _ => (),
+            }
+            if let Some(e) = &picker.last_error {
+                ui.colored_label(egui::Color32::LIGHT_RED, e);
+            }
+        });
+    Ok(())
+}
@@ -0,0 +1,158 @@
+//wavefront obj import, for models exported straight from cad without going through blender
+//registered as an asset loader so big cad exports are read on the asset server's background tasks like gltf models,
+//the obj and its mtl materials are parsed with tobj and put together into a scene like the gltf loader does,
+//so the rocket swaps to it the same way it swaps to a gltf model
+//
+//cad exports come in whatever units and position the assembly was drawn in, so the model is centered on the origin,
+//stood up with its longest side along y like the gltf models, and scaled to the size the camera is framed for
+
+use std::collections::HashMap;
+use std::io;
+use std::path::Path;
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, LoadContext, RenderAssetUsages};
+use bevy::mesh::{Indices, PrimitiveTopology};
+use bevy::prelude::*;
+
+const OBJ_MODEL_SIZE: f32 = 1.3; //length of the longest side after scaling, about as long as the low poly model
+
+//the rotation that stands the longest side of the bounding box up along y, positive end up
+fn stand_up_rotation(size: Vec3) -> Quat {
+    if size.x >= size.y && size.x >= size.z {
+        Quat::from_rotation_z(std::f32::consts::FRAC_PI_2)
+    } else if size.z > size.y {
+        Quat::from_rotation_x(-std::f32::consts::FRAC_PI_2)
+    } else {
+        Quat::IDENTITY
+    }
+}
+
+//material for an mtl material, textures are looked up next to the obj file
+fn standard_material(material: &tobj::Material, folder: &Path, load_context: &mut LoadContext) -> StandardMaterial {
+    let [r, g, b] = material.diffuse.unwrap_or([0.8, 0.8, 0.8]);
+    let alpha = material.dissolve.unwrap_or(1.0);
+    StandardMaterial {
+        base_color: Color::srgba(r, g, b, alpha),
+        base_color_texture: material.diffuse_texture.as_ref()
+            .map(|texture| load_context.load(folder.join(texture))),
+        alpha_mode: if alpha < 1.0 { AlphaMode::Blend } else { AlphaMode::Opaque },
+        ..default()
+    }
+}
+
+//builds a bevy mesh from an obj mesh, with every position and normal moved by the normalizing transform
+fn bevy_mesh(mesh: &tobj::Mesh, normalize: &Transform) -> Mesh {
+    let positions: Vec<[f32; 3]> = mesh.positions
+        .chunks_exact(3)
+        .map(|position| normalize.transform_point(Vec3::from_slice(position)).to_array())
+        .collect();
+    let mut bevy_mesh = Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::default());
+    bevy_mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    if !mesh.texcoords.is_empty() {
+        //obj texture coordinates start at the bottom of the image, bevy's at the top
+        let uvs: Vec<[f32; 2]> = mesh.texcoords.chunks_exact(2).map(|uv| [uv[0], 1.0 - uv[1]]).collect();
+        bevy_mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+    }
+    if mesh.normals.is_empty() {
+        //cad parts are mostly flat faces with sharp edges, flat normals keep them from looking melted
+        bevy_mesh.insert_indices(Indices::U32(mesh.indices.clone()));
+        bevy_mesh.duplicate_vertices();
+        bevy_mesh.compute_flat_normals();
+    } else {
+        let normals: Vec<[f32; 3]> = mesh.normals
+            .chunks_exact(3)
+            .map(|normal| (normalize.rotation * Vec3::from_slice(normal)).normalize_or_zero().to_array())
+            .collect();
+        bevy_mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+        bevy_mesh.insert_indices(Indices::U32(mesh.indices.clone()));
+    }
+    bevy_mesh
+}
+
+//asset loader for wavefront obj models, loads them as a scene
+#[derive(Default, TypePath)]
+pub struct ObjLoader;
+
+impl AssetLoader for ObjLoader {
+    type Asset = Scene;
+    type Settings = ();
+    type Error = io::Error;
+
+    //reads the obj and the mtl files it names, then builds the scene, centered, stood up, and scaled to the camera framing
+    //a missing or broken mtl file is not an error, the parts are drawn in plain grey instead
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<Scene, io::Error> {
+        let mut obj_bytes = vec![];
+        reader.read_to_end(&mut obj_bytes).await?;
+        let model_path = load_context.asset_path().path().to_path_buf();
+        let folder = model_path.parent().unwrap_or(Path::new("")).to_path_buf();
+
+        //tobj reads mtl files through a callback that can not wait on the asset server, so they are read first
+        let mut mtl_files = HashMap::new();
+        for line in String::from_utf8_lossy(&obj_bytes).lines() {
+            if let Some(name) = line.strip_prefix("mtllib ") {
+                let name = name.trim().to_string();
+                match load_context.read_asset_bytes(folder.join(&name)).await {
+                    Ok(bytes) => {
+                        mtl_files.insert(name, bytes);
+                    }
+                    Err(e) => warn!("Could not read the materials for {}, using plain grey: {}", model_path.display(), e),
+                }
+            }
+        }
+        let (models, obj_materials) = tobj::load_obj_buf(&mut obj_bytes.as_slice(), &tobj::GPU_LOAD_OPTIONS, |mtl_path| {
+            match mtl_files.get(mtl_path.to_string_lossy().as_ref()) {
+                Some(bytes) => tobj::load_mtl_buf(&mut bytes.as_slice()),
+                None => Err(tobj::LoadError::OpenFileFailed),
+            }
+        })
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{} is not a valid obj file: {}", model_path.display(), e)))?;
+        let obj_materials = obj_materials.unwrap_or_default();
+
+        //bounding box of every part together, so the parts stay where they are relative to each other
+        let (min, max) = models.iter()
+            .flat_map(|model| model.mesh.positions.chunks_exact(3))
+            .map(Vec3::from_slice)
+            .fold((Vec3::MAX, Vec3::MIN), |(min, max), position| (min.min(position), max.max(position)));
+        if min.cmpgt(max).any() {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("{} has no vertices", model_path.display())));
+        }
+        let size = max - min;
+        let rotation = stand_up_rotation(size);
+        let scale = OBJ_MODEL_SIZE / size.max_element().max(f32::EPSILON);
+        //moves the center of the box to the origin, then turns and scales about it
+        let normalize = Transform::from_rotation(rotation)
+            .with_scale(Vec3::splat(scale))
+            .with_translation(rotation * -(min + max) / 2.0 * scale);
+
+        let mut material_handles = vec![];
+        for (index, material) in obj_materials.iter().enumerate() {
+            let material = standard_material(material, &folder, load_context);
+            material_handles.push(load_context.add_labeled_asset(format!("Material{}", index), material));
+        }
+        let default_material = load_context.add_labeled_asset("DefaultMaterial".to_string(), StandardMaterial::default());
+
+        let mut world = World::new();
+        for (index, model) in models.iter().enumerate() {
+            let material = model.mesh.material_id
+                .and_then(|id| material_handles.get(id))
+                .unwrap_or(&default_material)
+                .clone();
+            let mesh = load_context.add_labeled_asset(format!("Mesh{}", index), bevy_mesh(&model.mesh, &normalize));
+            world.spawn((
+                Mesh3d(mesh),
+                MeshMaterial3d(material),
+                Name::new(model.name.clone()),
+            ));
+        }
+        Ok(Scene::new(world))
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["obj"]
+    }
+}
@@ -0,0 +1,111 @@
+//live plots of the telemetry channels, the quaternion components, tilt, heading and roll, and any numeric extra field the board sends
+//the points come from the telemetry history, which keeps the whole session so paused plots can be panned and zoomed back to the start
+
+use std::collections::HashSet;
+use bevy::prelude::*;
+use bevy_egui::{EguiContexts, egui};
+use egui_plot::{Legend, Line, Plot, PlotPoints};
+use crate::CurrentData;
+use crate::history::TelemetryHistory;
+
+const DEFAULT_TIME_WINDOW: f64 = 10.0; //seconds shown while following the newest sample
+const MAX_TIME_WINDOW: f64 = 600.0;
+const MAX_PLOT_POINTS: usize = 4_000; //points per channel handed to the plot, longer ranges are thinned out
+
+//resource that holds the plot settings, the points come from the telemetry history
+#[derive(Resource)]
+pub struct TelemetryPlots {
+    hidden: HashSet<String>, //channels the user turned off, new channels are shown until they are turned off
+    paused: bool, //stop following the newest sample so the plot can be panned and zoomed
+    time_window: f64, //seconds shown while following
+    visible_ms: (f64, f64), //timeline range the plot showed last frame, paused plots only get the points around it
+}
+
+impl Default for TelemetryPlots {
+    fn default() -> Self {
+        Self {
+            hidden: HashSet::new(),
+            paused: false,
+            time_window: DEFAULT_TIME_WINDOW,
+            visible_ms: (0.0, DEFAULT_TIME_WINDOW * 1000.0),
+        }
+    }
+}
+
+
+// UI SYSTEMS
+
+//plot ui system, runs every frame while in the monitoring or replay state
+//shows the channels the user picked, following the current sample unless paused
+//the sample closest to the mouse is shown under the plot
+pub fn ui_system_plots(
+    mut contexts: EguiContexts,
+    mut plots: ResMut<TelemetryPlots>,
+    history: Res<TelemetryHistory>,
+    current_data: Res<CurrentData>,
+) -> Result<(), BevyError> {
+    let ctx = contexts.ctx_mut()?;
+    egui::Window::new("Telemetry Plots")
+        .default_width(500.0)
+        .default_open(false)
+        .show(ctx, |ui| {
+            let plots = &mut *plots;
+            ui.horizontal(|ui| {
+                let pause_label = if plots.paused { "Follow" } else { "Pause" };
+                if ui.button(pause_label).clicked() {
+                    plots.paused = !plots.paused;
+                }
+                ui.label("Window:");
+                ui.add(egui::DragValue::new(&mut plots.time_window).range(1.0..=MAX_TIME_WINDOW).suffix(" s"));
+            });
+            //channel picker
+            let channel_names = history.channel_names();
+            ui.horizontal_wrapped(|ui| {
+                for name in &channel_names {
+                    let mut shown = !plots.hidden.contains(name);
+                    if ui.checkbox(&mut shown, name).changed() {
+                        if shown {
+                            plots.hidden.remove(name);
+                        } else {
+                            plots.hidden.insert(name.clone());
+                        }
+                    }
+                }
+            });
+            //the replay has the whole recording in the history, so the plot follows the current sample rather than the newest
+            let end_ms = current_data.timeline_ms;
+            let start_ms = end_ms - plots.time_window * 1000.0;
+            //while following only the points inside the window are handed to the plot
+            //paused plots get the range that was visible last frame with half a screen either side, so panning does not show gaps
+            let (first_ms, last_ms) = if plots.paused {
+                let (visible_start, visible_end) = plots.visible_ms;
+                let margin = (visible_end - visible_start) / 2.0;
+                (visible_start - margin, visible_end + margin)
+            } else {
+                (start_ms, end_ms)
+            };
+            let pointer = Plot::new("telemetry_plot")
+                .legend(Legend::default())
+                .height(250.0)
+                .allow_drag(plots.paused)
+                .allow_zoom(plots.paused)
+                .allow_scroll(plots.paused)
+                .x_axis_label("Time (s)")
+                .show(ui, |plot_ui| {
+                    for name in channel_names.iter().filter(|name| !plots.hidden.contains(*name)) {
+                        plot_ui.line(Line::new(name.clone(), PlotPoints::from(history.channel(name, first_ms, last_ms, MAX_PLOT_POINTS))));
+                    }
+                    if !plots.paused {
+                        plot_ui.set_plot_bounds_x(start_ms / 1000.0..=end_ms / 1000.0);
+                    }
+                    let bounds = plot_ui.plot_bounds();
+                    plots.visible_ms = (bounds.min()[0] * 1000.0, bounds.max()[0] * 1000.0);
+                    plot_ui.pointer_coordinate()
+                })
+                .inner;
+            if let Some(sample) = pointer.and_then(|pointer| history.nearest(pointer.x * 1000.0)) {
+                ui.label(format!("{:.3}s, device time {}", sample.timeline_ms / 1000.0, sample.data.time));
+            }
+        });
+    Ok(())
+}
@@ -0,0 +1,289 @@
+//raw capture, every chunk of bytes a byte stream source hands to the reader thread is written to a capture file exactly as it arrived
+//so when the parser gets something wrong the bytes the board actually sent are still around to reproduce it with
+//
+//the file starts with RAW_CAPTURE_MAGIC followed by one entry per chunk:
+//the receive time as microseconds since the session started (u64, little endian), the chunk length (u32, little endian), then the chunk itself
+//when the source reconnects the reader thread throws away the unfinished line, that is written as an entry with DISCONNECT_MARKER
+//as its length and no bytes, so a replay drops the same half line at the same place
+//the reader thread writes the file itself, a crash can cut off the last chunk and load_raw_capture drops it
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use bevy::prelude::*;
+use crate::session_log::{FLUSH_INTERVAL, FileStatus, SESSION_DIRECTORY};
+
+const RAW_CAPTURE_MAGIC: &[u8] = b"rocketviewer-raw-v1\n"; //first bytes of every capture file
+const CHUNK_HEADER_LENGTH: usize = 12; //receive time and chunk length
+const DISCONNECT_MARKER: u32 = u32::MAX; //chunk length of a disconnect entry, no real chunk is this long
+
+//a single entry of a raw capture, times are microseconds since the session started
+#[derive(Debug, PartialEq)]
+pub enum RawEntry {
+    Chunk { host_time_us: u64, bytes: Vec<u8> }, //a chunk of bytes as it was read from the source
+    Disconnect { host_time_us: u64 }, //the source reconnected and the unfinished line was thrown away
+}
+
+impl RawEntry {
+    //when the entry was written
+    pub fn host_time_us(&self) -> u64 {
+        match self {
+            RawEntry::Chunk { host_time_us, .. } | RawEntry::Disconnect { host_time_us } => *host_time_us,
+        }
+    }
+}
+
+//resource that shows the state of the raw capture in the ui, the reader thread owns the file itself
+#[derive(Resource)]
+pub struct RawCaptureStatus(pub FileStatus);
+
+//writing end of a raw capture, handed to the reader thread
+pub struct RawCaptureWriter {
+    writer: Option<BufWriter<File>>, //none once writing has failed
+    last_flush: Instant,
+    status: FileStatus, //bytes captured so far and the error that stopped capturing, shared with the ui
+}
+
+impl RawCaptureWriter {
+    //creates a new capture file named after the session start time next to the session logs
+    //returns the status for the ui and the writer for the reader thread, the writer is none if the file could not be created
+    pub fn start(start_wall_ms: u64) -> (RawCaptureStatus, Option<RawCaptureWriter>) {
+        let path = PathBuf::from(SESSION_DIRECTORY).join(format!("session-{}.raw", start_wall_ms));
+        if let Err(e) = fs::create_dir_all(SESSION_DIRECTORY) {
+            let status = FileStatus::new(path.clone(), "Capturing raw bytes", "bytes");
+            status.fail(format!("Could not create {}: {}", path.display(), e));
+            return (RawCaptureStatus(status), None);
+        }
+        Self::create(path)
+    }
+
+    //creates the capture file at path and writes the magic bytes to it
+    fn create(path: PathBuf) -> (RawCaptureStatus, Option<RawCaptureWriter>) {
+        let status = FileStatus::new(path.clone(), "Capturing raw bytes", "bytes");
+        //create_new so an existing capture is never overwritten
+        let file = OpenOptions::new().create_new(true).append(true).open(&path)
+            .and_then(|file| {
+                let mut writer = BufWriter::new(file);
+                writer.write_all(RAW_CAPTURE_MAGIC)?;
+                writer.flush()?;
+                Ok(writer)
+            });
+        match file {
+            Ok(writer) => {
+                let capture = RawCaptureWriter {
+                    writer: Some(writer),
+                    last_flush: Instant::now(),
+                    status: status.clone(),
+                };
+                (RawCaptureStatus(status), Some(capture))
+            }
+            Err(e) => {
+                status.fail(format!("Could not create {}: {}", path.display(), e));
+                (RawCaptureStatus(status), None)
+            }
+        }
+    }
+
+    //appends a chunk to the capture file, empty reads are not chunks and are skipped
+    pub fn write_chunk(&mut self, host_time_us: u64, chunk: &[u8]) {
+        if chunk.is_empty() {
+            return;
+        }
+        self.write_entry(host_time_us, chunk.len() as u32, chunk);
+    }
+
+    //appends a disconnect entry, written when the reader thread throws away the unfinished line after a reconnect
+    pub fn write_disconnect(&mut self, host_time_us: u64) {
+        self.write_entry(host_time_us, DISCONNECT_MARKER, &[]);
+    }
+
+    //appends an entry header and its bytes
+    //if writing fails the error is kept for the ui and the rest of the session is not captured
+    fn write_entry(&mut self, host_time_us: u64, length: u32, chunk: &[u8]) {
+        let Some(writer) = &mut self.writer else {
+            return;
+        };
+        let result = writer.write_all(&host_time_us.to_le_bytes())
+            .and_then(|()| writer.write_all(&length.to_le_bytes()))
+            .and_then(|()| writer.write_all(chunk))
+            .and_then(|()| {
+                if self.last_flush.elapsed() >= FLUSH_INTERVAL {
+                    self.last_flush = Instant::now();
+                    writer.flush()?;
+                }
+                Ok(())
+            });
+        match result {
+            Ok(()) => self.status.add_written(chunk.len() as u64),
+            Err(e) => {
+                self.status.fail(format!("Could not write to {}: {}", self.status.path().display(), e));
+                self.writer = None;
+            }
+        }
+    }
+}
+
+//make sure the last chunks make it to disk when the reader thread stops
+impl Drop for RawCaptureWriter {
+    fn drop(&mut self) {
+        if let Some(writer) = &mut self.writer {
+            let _ = writer.flush();
+            let _ = writer.get_ref().sync_all();
+        }
+    }
+}
+
+//raw capture file read back from disk
+pub struct LoadedRawCapture {
+    pub entries: Vec<RawEntry>,
+    pub truncated_bytes: usize, //size of a final chunk that was cut off in the middle of being written
+}
+
+//reads a raw capture file, dropping the last entry if it was cut off by a crash
+pub fn load_raw_capture(path: &Path) -> io::Result<LoadedRawCapture> {
+    let contents = fs::read(path)
+        .map_err(|e| io::Error::new(e.kind(), format!("Could not read {}: {}", path.display(), e)))?;
+    let Some(mut remaining) = contents.strip_prefix(RAW_CAPTURE_MAGIC) else {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, format!("{} is not a raw capture file", path.display())));
+    };
+    let mut entries = vec![];
+    while remaining.len() >= CHUNK_HEADER_LENGTH {
+        let (header, rest) = remaining.split_at(CHUNK_HEADER_LENGTH);
+        let host_time_us = u64::from_le_bytes(header[..8].try_into().expect("header is 12 bytes"));
+        let length = u32::from_le_bytes(header[8..].try_into().expect("header is 12 bytes"));
+        if length == DISCONNECT_MARKER {
+            entries.push(RawEntry::Disconnect { host_time_us });
+            remaining = rest;
+            continue;
+        }
+        let length = length as usize;
+        if rest.len() < length {
+            break;
+        }
+        let (bytes, rest) = rest.split_at(length);
+        entries.push(RawEntry::Chunk {
+            host_time_us,
+            bytes: bytes.to_vec(),
+        });
+        remaining = rest;
+    }
+    Ok(LoadedRawCapture {
+        entries,
+        truncated_bytes: remaining.len(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //a file in the temp folder that is deleted again when the test is done with it
+    struct TempFile(PathBuf);
+
+    impl TempFile {
+        fn new(name: &str) -> Self {
+            Self(std::env::temp_dir().join(format!("rocketviewer-{}-raw-{}", std::process::id(), name)))
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    //chunks and disconnects the way the reader thread writes them, including a chunk that ends in the middle of a line
+    fn entries() -> Vec<RawEntry> {
+        vec![
+            RawEntry::Chunk { host_time_us: 1_000, bytes: b"{\"x\":0.1,\"y\":0".to_vec() },
+            RawEntry::Chunk { host_time_us: 2_500, bytes: b".2}\n{\"x\":".to_vec() },
+            RawEntry::Disconnect { host_time_us: 900_000 },
+            RawEntry::Chunk { host_time_us: 1_200_000, bytes: vec![0xff, 0x00, b'\n'] },
+            RawEntry::Disconnect { host_time_us: 1_300_000 },
+            RawEntry::Chunk { host_time_us: u64::MAX, bytes: b"{}\n".to_vec() },
+        ]
+    }
+
+    //writes the entries through the reader thread's writer and returns the file's bytes
+    fn capture_bytes(name: &str, entries: &[RawEntry]) -> Vec<u8> {
+        let file = TempFile::new(name);
+        let (status, writer) = RawCaptureWriter::create(file.0.clone());
+        let mut writer = writer.unwrap();
+        for entry in entries {
+            match entry {
+                RawEntry::Chunk { host_time_us, bytes } => writer.write_chunk(*host_time_us, bytes),
+                RawEntry::Disconnect { host_time_us } => writer.write_disconnect(*host_time_us),
+            }
+        }
+        drop(writer);
+        assert!(!status.0.has_failed());
+        fs::read(&file.0).unwrap()
+    }
+
+    //size of an entry in the file
+    fn entry_length(entry: &RawEntry) -> usize {
+        match entry {
+            RawEntry::Chunk { bytes, .. } => CHUNK_HEADER_LENGTH + bytes.len(),
+            RawEntry::Disconnect { .. } => CHUNK_HEADER_LENGTH,
+        }
+    }
+
+    #[test]
+    fn round_trip_keeps_chunks_and_disconnects() {
+        let bytes = capture_bytes("round-trip", &entries());
+        let file = TempFile::new("round-trip-copy");
+        fs::write(&file.0, &bytes).unwrap();
+        let capture = load_raw_capture(&file.0).unwrap();
+        assert_eq!(capture.entries, entries());
+        assert_eq!(capture.truncated_bytes, 0);
+    }
+
+    #[test]
+    fn empty_reads_are_not_written() {
+        let file = TempFile::new("empty-reads");
+        let (status, writer) = RawCaptureWriter::create(file.0.clone());
+        let mut writer = writer.unwrap();
+        writer.write_chunk(10, &[]);
+        writer.write_chunk(20, b"a");
+        drop(writer);
+        assert_eq!(status.0.summary(), format!("Capturing raw bytes to {} (1 bytes)", file.0.display()));
+        let capture = load_raw_capture(&file.0).unwrap();
+        assert_eq!(capture.entries, vec![RawEntry::Chunk { host_time_us: 20, bytes: b"a".to_vec() }]);
+    }
+
+    #[test]
+    fn truncated_at_every_offset_recovers_complete_entries() {
+        let entries = entries();
+        let bytes = capture_bytes("truncated", &entries);
+        let file = TempFile::new("truncated-copy");
+        for cut in 0..=bytes.len() {
+            fs::write(&file.0, &bytes[..cut]).unwrap();
+            let loaded = load_raw_capture(&file.0);
+            if cut < RAW_CAPTURE_MAGIC.len() {
+                assert!(matches!(loaded, Err(e) if e.kind() == io::ErrorKind::InvalidData), "cut at {}", cut);
+                continue;
+            }
+            let capture = loaded.unwrap();
+            //every entry that ends before the cut is kept, whatever is left after the last one is the cut off entry
+            let mut end = RAW_CAPTURE_MAGIC.len();
+            let mut complete = 0;
+            for entry in &entries {
+                if end + entry_length(entry) > cut {
+                    break;
+                }
+                end += entry_length(entry);
+                complete += 1;
+            }
+            assert_eq!(capture.entries, entries[..complete], "cut at {}", cut);
+            assert_eq!(capture.truncated_bytes, cut - end, "cut at {}", cut);
+        }
+    }
+
+    #[test]
+    fn file_without_magic_is_rejected() {
+        let file = TempFile::new("not-a-capture");
+        fs::write(&file.0, b"{\"x\":0.1}\n").unwrap();
+        assert!(matches!(load_raw_capture(&file.0), Err(e) if e.kind() == io::ErrorKind::InvalidData));
+    }
+}
@@ -0,0 +1,195 @@
+//replay of recorded telemetry files
+//a recording is either a session file written while monitoring or a file of json lines in the same format the arduino sends,
+//the whole recording is loaded into the telemetry history up front, the replay then drives CurrentData from the recorded time field
+//so the rocket model and the monitor window behave the same as they do with live data
+
+use std::io;
+use std::path::Path;
+use bevy::prelude::*;
+use bevy_egui::{EguiContexts, egui};
+use crate::{AppState, CurrentData, ErrorCounters, MonitorError, MonitorErrorKind, SerialMonitorSelection};
+use crate::calibration::{Calibration, WorldTransform};
+use crate::frame::FrameMapping;
+use crate::history::TelemetryHistory;
+use crate::session_log::{SessionHeader, load_session};
+
+const MIN_REPLAY_SPEED: f32 = 0.1;
+const MAX_REPLAY_SPEED: f32 = 10.0;
+
+//resource that holds the recording being replayed and the playback state
+#[derive(Resource)]
+pub struct ReplaySession {
+    path: String,
+    header: Option<SessionHeader>, //metadata of the session the recording came from, none for plain recordings
+    skipped_lines: usize, //lines in the file that could not be parsed
+    truncated_bytes: usize, //size of the cut off last record that was dropped when the file was recovered
+    position_ms: f64, //current playback position on the history timeline
+    playing: bool,
+    speed: f32, //playback speed, 1.0 is real time
+}
+
+impl ReplaySession {
+    //loads a recording from disk into the history, lines that can not be parsed are skipped and counted
+    //session records that were flagged as unparsable when they were received are counted as skipped too
+    //sessions are recorded as they were received, so the samples are put through the frame mapping and calibration the same way live ones are
+    pub fn load(path: &str, history: &mut TelemetryHistory, frame_mapping: &FrameMapping, calibration: &Calibration) -> io::Result<Self> {
+        let session = load_session(Path::new(path))?;
+        history.reset();
+        let mut skipped_lines = session.skipped_lines;
+        let transform = WorldTransform::new(frame_mapping, calibration);
+        for record in session.records {
+            let Some(data) = record.data else {
+                skipped_lines += 1;
+                continue;
+            };
+            let quat = transform.apply(&data);
+            history.push(data, record.host_time_us, quat);
+        }
+        if history.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("{} does not contain any telemetry", path)));
+        }
+        Ok(Self {
+            path: path.to_string(),
+            header: session.header,
+            skipped_lines,
+            truncated_bytes: session.truncated_bytes,
+            position_ms: 0.0,
+            playing: true,
+            speed: 1.0,
+        })
+    }
+
+}
+
+//length of the recording in milliseconds
+fn duration_ms(history: &TelemetryHistory) -> f64 {
+    history.latest().map_or(0.0, |sample| sample.timeline_ms)
+}
+
+
+// SETUP SYSTEMS
+
+//replay setup system, will run when app state switches to replay
+//loads the selected recording into the history and inserts the current data resource, moves the app to the error state if the file can not be loaded
+pub fn setup_replay(
+    mut commands: Commands,
+    selection: Res<SerialMonitorSelection>,
+    frame_mapping: Res<FrameMapping>,
+    calibration: Res<Calibration>,
+    mut history: ResMut<TelemetryHistory>,
+    mut error_counters: ResMut<ErrorCounters>,
+    mut app_state: ResMut<NextState<AppState>>,
+) {
+    match ReplaySession::load(&selection.replay_path, &mut history, &frame_mapping, &calibration) {
+        Ok(session) => {
+            if let Some(first) = history.first() {
+                commands.insert_resource(CurrentData {
+                    quat: first.quat,
+                    time: first.data.time,
+                    timeline_ms: first.timeline_ms,
+                });
+            }
+            commands.insert_resource(session);
+        }
+        Err(e) => {
+            error_counters.add(MonitorErrorKind::Open, 1);
+            commands.insert_resource(MonitorError {
+                kind: MonitorErrorKind::Open,
+                message: e.to_string(),
+                retry_state: AppState::Replay,
+            });
+            app_state.set(AppState::Error);
+        }
+    }
+}
+
+//replay teardown system, will run when app state switches away from replay
+pub fn teardown_replay(
+    mut commands: Commands,
+) {
+    commands.remove_resource::<ReplaySession>();
+    commands.remove_resource::<CurrentData>();
+}
+
+
+// UPDATE SYSTEMS
+
+//replay update system, runs every frame while in the replay state
+//moves the playback position forward by the frame time scaled by the playback speed and updates the current data resource
+//with the newest sample at or before the playback position
+pub fn advance_replay(
+    time: Res<Time>,
+    history: Res<TelemetryHistory>,
+    mut session: ResMut<ReplaySession>,
+    mut current_data: ResMut<CurrentData>,
+) {
+    if session.playing {
+        let duration_ms = duration_ms(&history);
+        session.position_ms += time.delta_secs_f64() * 1000.0 * session.speed as f64;
+        //stop at the end instead of running off the timeline
+        if session.position_ms >= duration_ms {
+            session.position_ms = duration_ms;
+            session.playing = false;
+        }
+    }
+    if let Some(sample) = history.at_or_before(session.position_ms) {
+        current_data.quat = sample.quat;
+        current_data.time = sample.data.time;
+        current_data.timeline_ms = sample.timeline_ms;
+    }
+}
+
+
+// UI SYSTEMS
+
+//replay ui system, runs every frame while in the replay state
+//shows the timeline scrubber and the play, pause and speed controls
+pub fn ui_system_replay(
+    mut contexts: EguiContexts,
+    mut session: ResMut<ReplaySession>,
+    history: Res<TelemetryHistory>,
+    mut app_state: ResMut<NextState<AppState>>,
+) -> Result<(), BevyError> {
+    let ctx = contexts.ctx_mut()?;
+    egui::Window::new("Replay")
+        .default_width(400.0)
+        .show(ctx, |ui| {
+            ui.label(format!("File: {}", session.path));
+            if let Some(header) = &session.header {
+                ui.label(format!("Recorded from {} with version {}", header.source, header.app_version));
+            }
+            ui.label(format!("Samples: {} ({} unreadable lines skipped)", history.len(), session.skipped_lines));
+            if session.truncated_bytes > 0 {
+                ui.colored_label(egui::Color32::YELLOW, format!("Recovered: dropped {} bytes of a record that was cut off", session.truncated_bytes));
+            }
+            //timeline scrubber, dragging it seeks
+            let duration_ms = duration_ms(&history);
+            ui.horizontal(|ui| {
+                ui.spacing_mut().slider_width = 300.0;
+                ui.add(egui::Slider::new(&mut session.position_ms, 0.0..=duration_ms)
+                    .show_value(false));
+                ui.label(format!("{:.2}s / {:.2}s", session.position_ms / 1000.0, duration_ms / 1000.0));
+            });
+            ui.horizontal(|ui| {
+                let play_label = if session.playing { "Pause" } else { "Play" };
+                if ui.button(play_label).clicked() {
+                    //playing from the end starts over from the beginning
+                    if !session.playing && session.position_ms >= duration_ms {
+                        session.position_ms = 0.0;
+                    }
+                    session.playing = !session.playing;
+                }
+                if ui.button("Restart").clicked() {
+                    session.position_ms = 0.0;
+                }
+                ui.add(egui::Slider::new(&mut session.speed, MIN_REPLAY_SPEED..=MAX_REPLAY_SPEED)
+                    .logarithmic(true)
+                    .text("Speed")
+                    .suffix("x"));
+            });
+            if ui.button("Stop Replay").clicked() {
+                app_state.set(AppState::Idle);
+            }
+        });
+    Ok(())
+}
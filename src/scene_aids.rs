@@ -0,0 +1,154 @@
+//scene aids, reference lines drawn around the rocket so its attitude can be judged by eye
+//a ground grid, the world axes, the rocket's body axes, a vertical line through the pad, and a cone showing the allowed tilt
+//everything is drawn with gizmos every frame, the axis labels are drawn by egui on top of the scene
+
+use std::f32::consts::{FRAC_PI_2, TAU};
+use bevy::prelude::*;
+use bevy_egui::{EguiContexts, egui};
+use crate::Rocket;
+
+const GRID_CELLS: u32 = 20;
+const GRID_SPACING: f32 = 0.25; //distances between grid lines, the rocket is about one long
+const WORLD_AXIS_LENGTH: f32 = 1.0;
+const BODY_AXIS_LENGTH: f32 = 0.6;
+const VERTICAL_LENGTH: f32 = 1.5;
+const TILT_CONE_HEIGHT: f32 = 0.8;
+const TILT_CONE_SIDES: usize = 8; //lines drawn from the tip of the cone to its rim
+const DEFAULT_TILT_LIMIT: f32 = 15.0; //degrees
+const LABEL_OFFSET: f32 = 1.08; //labels sit just past the ends of the axes
+
+//resource that holds which scene aids are shown
+#[derive(Resource, Debug)]
+pub struct SceneAids {
+    pub grid: bool,
+    pub world_axes: bool,
+    pub body_axes: bool,
+    pub vertical: bool,
+    pub tilt_cone: bool,
+    pub tilt_limit: f32, //degrees off vertical the rocket is allowed to lean, the cone turns red past it
+}
+
+impl Default for SceneAids {
+    fn default() -> Self {
+        Self {
+            grid: true,
+            world_axes: true,
+            body_axes: false,
+            vertical: false,
+            tilt_cone: false,
+            tilt_limit: DEFAULT_TILT_LIMIT,
+        }
+    }
+}
+
+//world axes with their labels and colors, x red, y green, z blue like most 3d tools
+fn world_axes() -> [(Vec3, &'static str, Color); 3] {
+    [
+        (Vec3::X, "X", Color::srgb(0.9, 0.2, 0.2)),
+        (Vec3::Y, "Y", Color::srgb(0.2, 0.9, 0.2)),
+        (Vec3::Z, "Z", Color::srgb(0.2, 0.4, 1.0)),
+    ]
+}
+
+//degrees the rocket's long axis, its body y axis, leans away from straight up
+pub fn tilt_degrees(rotation: Quat) -> f32 {
+    (rotation * Vec3::Y).angle_between(Vec3::Y).to_degrees()
+}
+
+
+// UPDATE SYSTEMS
+
+//scene aids update system, runs every frame after the rocket has been turned
+//gizmos only last one frame so everything is drawn again each time
+pub fn draw_scene_aids(
+    mut gizmos: Gizmos,
+    aids: Res<SceneAids>,
+    query: Query<&Transform, With<Rocket>>,
+) {
+    let rocket = query.single().copied().unwrap_or_default();
+    if aids.grid {
+        //the grid is drawn in its own xy plane, turned to lie flat on the ground
+        gizmos.grid(
+            Isometry3d::from_rotation(Quat::from_rotation_x(FRAC_PI_2)),
+            UVec2::splat(GRID_CELLS),
+            Vec2::splat(GRID_SPACING),
+            Color::srgb(0.35, 0.35, 0.35),
+        );
+    }
+    if aids.world_axes {
+        for (axis, _, color) in world_axes() {
+            gizmos.arrow(Vec3::ZERO, axis * WORLD_AXIS_LENGTH, color);
+        }
+    }
+    if aids.body_axes {
+        gizmos.axes(rocket, BODY_AXIS_LENGTH);
+    }
+    if aids.vertical {
+        gizmos.line(rocket.translation, rocket.translation + Vec3::Y * VERTICAL_LENGTH, Color::WHITE);
+    }
+    if aids.tilt_cone {
+        //green while the rocket is inside the limit, red once it leans past it
+        let color = if tilt_degrees(rocket.rotation) <= aids.tilt_limit {
+            Color::srgb(0.2, 0.9, 0.2)
+        } else {
+            Color::srgb(0.9, 0.2, 0.2)
+        };
+        let tip = rocket.translation;
+        let rim_center = tip + Vec3::Y * TILT_CONE_HEIGHT;
+        let radius = TILT_CONE_HEIGHT * aids.tilt_limit.to_radians().tan();
+        gizmos.circle(Isometry3d::new(rim_center, Quat::from_rotation_x(FRAC_PI_2)), radius, color);
+        for side in 0..TILT_CONE_SIDES {
+            let angle = side as f32 * TAU / TILT_CONE_SIDES as f32;
+            gizmos.line(tip, rim_center + Vec3::new(angle.cos(), 0.0, angle.sin()) * radius, color);
+        }
+        //the rocket's long axis, to compare against the cone
+        gizmos.line(tip, tip + rocket.rotation * Vec3::Y * TILT_CONE_HEIGHT, Color::WHITE);
+    }
+}
+
+
+// UI SYSTEMS
+
+//scene aids ui system, runs every frame
+//toggles for each aid, the tilt limit, and the world axis labels drawn over the scene
+pub fn ui_system_scene_aids(
+    mut contexts: EguiContexts,
+    mut aids: ResMut<SceneAids>,
+    camera_query: Query<(&Camera, &GlobalTransform)>,
+    rocket_query: Query<&Transform, With<Rocket>>,
+) -> Result<(), BevyError> {
+    let ctx = contexts.ctx_mut()?;
+    let rocket = rocket_query.single().copied().unwrap_or_default();
+    egui::Window::new("Scene Aids")
+        .default_width(200.0)
+        .default_open(false)
+        .show(ctx, |ui| {
+            ui.checkbox(&mut aids.grid, "Ground Grid");
+            ui.checkbox(&mut aids.world_axes, "World Axes");
+            ui.checkbox(&mut aids.body_axes, "Body Axes");
+            ui.checkbox(&mut aids.vertical, "Vertical Reference");
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut aids.tilt_cone, "Tilt Cone");
+                ui.add(egui::DragValue::new(&mut aids.tilt_limit).range(1.0..=80.0).speed(0.5).suffix("°"));
+            });
+            ui.label(format!("Tilt: {:.1}°", tilt_degrees(rocket.rotation)));
+        });
+    //labels at the ends of the world axes, skipped for any end that is off screen or behind the camera
+    if aids.world_axes && let Ok((camera, camera_transform)) = camera_query.single() {
+        let painter = ctx.layer_painter(egui::LayerId::background());
+        for (axis, label, color) in world_axes() {
+            let Ok(position) = camera.world_to_viewport(camera_transform, axis * WORLD_AXIS_LENGTH * LABEL_OFFSET) else {
+                continue;
+            };
+            let [r, g, b, _] = color.to_srgba().to_u8_array();
+            painter.text(
+                egui::pos2(position.x, position.y),
+                egui::Align2::CENTER_CENTER,
+                label,
+                egui::FontId::proportional(16.0),
+                egui::Color32::from_rgb(r, g, b),
+            );
+        }
+    }
+    Ok(())
+}
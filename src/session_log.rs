@@ -0,0 +1,425 @@
+//session logging, every line received while monitoring is written to a session file as the record of the flight
+//the file starts with a header line holding the session metadata, followed by one json record per received line holding
+//the raw text, the parsed data, and when the line was received by this computer
+//lines that could not be parsed are kept and flagged with the parse error instead of being dropped
+//
+//the file is only ever appended to, from a background writer thread that flushes and syncs it to disk regularly,
+//so if the app is killed or the laptop crashes at most the last moments are lost and the last record may be cut off
+//load_session recovers such files by dropping the cut off record
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::ArduinoData;
+
+pub const SESSION_DIRECTORY: &str = "sessions"; //folder session files and raw captures are written to, relative to the working directory
+const SESSION_FORMAT: &str = "rocketviewer-session-v1"; //marks the header line, bump if the record format ever changes
+pub const FLUSH_INTERVAL: Duration = Duration::from_millis(200); //how often buffered records and raw chunks are handed to the operating system
+const SYNC_INTERVAL: Duration = Duration::from_secs(1); //how often the operating system is made to put the file on disk
+
+//first line of a session file, describes where the data came from
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SessionHeader {
+    pub format: String, //always SESSION_FORMAT, used to tell the header apart from records
+    pub app_version: String,
+    pub source: String, //description of the telemetry source
+    pub port: Option<String>, //serial port name, none for other sources
+    pub baud_rate: Option<u32>, //serial baud rate, none for other sources
+    pub start_wall_ms: u64, //wall clock milliseconds since the unix epoch when the session started
+}
+
+impl SessionHeader {
+    //header for a session starting now, port and baud rate are only known for serial sources
+    pub fn new(source: String, port: Option<String>, baud_rate: Option<u32>) -> Self {
+        Self {
+            format: SESSION_FORMAT.to_string(),
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            source,
+            port,
+            baud_rate,
+            start_wall_ms: wall_time_ms(),
+        }
+    }
+}
+
+//a single received line as it is written to the session file
+#[derive(Serialize, Deserialize, Debug)]
+pub struct LogRecord {
+    pub host_time_us: u64, //microseconds since the session started, from the monotonic clock so it never jumps
+    pub wall_time_ms: u64, //wall clock milliseconds since the unix epoch when the line was received
+    pub raw: String, //the line exactly as it was received, minus the newline
+    pub data: Option<ArduinoData>, //the parsed line, none if it could not be parsed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parse_error: Option<String>, //why the line could not be parsed
+}
+
+//state of a file that is written in the background, shared between the side writing it and the ui
+//used by the session log and the raw capture, the writing side counts what it wrote and keeps the error that stopped it
+#[derive(Clone)]
+pub struct FileStatus {
+    path: PathBuf,
+    activity: &'static str, //what is being done with the file, shown in the summary
+    unit: &'static str, //what is counted, shown in the summary
+    written: Arc<AtomicU64>, //how much has been written so far
+    error: Arc<Mutex<Option<String>>>, //set if the file could not be created or written
+}
+
+impl FileStatus {
+    //status of a file at path that nothing has been written to yet
+    pub fn new(path: PathBuf, activity: &'static str, unit: &'static str) -> Self {
+        Self {
+            path,
+            activity,
+            unit,
+            written: Arc::new(AtomicU64::new(0)),
+            error: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    //the file being written
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    //counts what was just written to the file
+    pub fn add_written(&self, count: u64) {
+        self.written.fetch_add(count, Ordering::Relaxed);
+    }
+
+    //keeps the error that stopped writing for the ui
+    pub fn fail(&self, message: String) {
+        if let Ok(mut error) = self.error.lock() {
+            *error = Some(message);
+        }
+    }
+
+    //one line summary of the file for the ui
+    pub fn summary(&self) -> String {
+        match self.error_message() {
+            Some(error) => format!("Not {}: {}", self.activity.to_lowercase(), error),
+            None => format!("{} to {} ({} {})", self.activity, self.path.display(), self.written.load(Ordering::Relaxed), self.unit),
+        }
+    }
+
+    //true if writing has stopped because of an error
+    pub fn has_failed(&self) -> bool {
+        self.error_message().is_some()
+    }
+
+    //the error that stopped writing, if there is one
+    fn error_message(&self) -> Option<String> {
+        self.error.lock().ok().and_then(|error| error.clone())
+    }
+}
+
+//resource that holds the session file for the current monitoring session
+//records are handed to the writer thread, if the file could not be created or written the error is kept for the ui
+//and monitoring carries on without a log
+#[derive(Resource)]
+pub struct SessionLog {
+    sender: Option<Sender<LogRecord>>, //none once the log is closed or could not be created
+    writer_thread: Option<JoinHandle<()>>,
+    status: FileStatus, //records written so far and the error that stopped the writer thread
+}
+
+impl SessionLog {
+    //creates a new session file named after the current time in the session folder and starts the writer thread
+    //the header is the first thing the writer thread writes
+    pub fn start(header: SessionHeader) -> Self {
+        let path = PathBuf::from(SESSION_DIRECTORY).join(format!("session-{}.jsonl", header.start_wall_ms));
+        let status = FileStatus::new(path.clone(), "Logging", "records");
+        //create_new so an existing session is never overwritten, append so every write goes to the end of the file
+        let file = fs::create_dir_all(SESSION_DIRECTORY)
+            .and_then(|()| OpenOptions::new().create_new(true).append(true).open(&path));
+        let (sender, writer_thread) = match file {
+            Ok(file) => {
+                let (sender, receiver) = mpsc::channel();
+                let thread_status = status.clone();
+                let writer_thread = thread::spawn(move || {
+                    if let Err(e) = write_session(file, &header, receiver, &thread_status) {
+                        thread_status.fail(format!("Could not write to {}: {}", thread_status.path().display(), e));
+                    }
+                });
+                (Some(sender), Some(writer_thread))
+            }
+            Err(e) => {
+                status.fail(format!("Could not create {}: {}", path.display(), e));
+                (None, None)
+            }
+        };
+        Self {
+            sender,
+            writer_thread,
+            status,
+        }
+    }
+
+    //hands a record to the writer thread to be appended to the session file
+    pub fn write(&mut self, record: LogRecord) {
+        if let Some(sender) = &self.sender {
+            //a failed send means the writer thread stopped because of an error, which it has already recorded
+            let _ = sender.send(record);
+        }
+    }
+
+    //records written so far and whether logging has stopped, for the ui
+    pub fn status(&self) -> &FileStatus {
+        &self.status
+    }
+}
+
+//closing the channel tells the writer thread to write out what is left, sync the file, and stop
+//waiting for it means the file is complete on disk once the log is gone
+impl Drop for SessionLog {
+    fn drop(&mut self) {
+        self.sender = None;
+        if let Some(writer_thread) = self.writer_thread.take() {
+            let _ = writer_thread.join();
+        }
+    }
+}
+
+//writer thread body, writes the header then every record it receives until the channel is closed
+//flushes the buffer every FLUSH_INTERVAL and syncs the file to disk every SYNC_INTERVAL
+fn write_session(
+    file: File,
+    header: &SessionHeader,
+    receiver: Receiver<LogRecord>,
+    status: &FileStatus,
+) -> io::Result<()> {
+    let mut writer = BufWriter::new(file);
+    write_line(&mut writer, header)?;
+    writer.flush()?;
+    writer.get_ref().sync_data()?;
+    let mut last_flush = Instant::now();
+    let mut last_sync = Instant::now();
+    loop {
+        match receiver.recv_timeout(FLUSH_INTERVAL) {
+            Ok(record) => {
+                write_line(&mut writer, &record)?;
+                status.add_written(1);
+            }
+            Err(RecvTimeoutError::Timeout) => (),
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+        if last_flush.elapsed() >= FLUSH_INTERVAL {
+            writer.flush()?;
+            last_flush = Instant::now();
+        }
+        if last_sync.elapsed() >= SYNC_INTERVAL {
+            writer.flush()?;
+            writer.get_ref().sync_data()?;
+            last_sync = Instant::now();
+        }
+    }
+    writer.flush()?;
+    writer.get_ref().sync_all()
+}
+
+//writes a value as a single json line
+fn write_line<T: Serialize>(writer: &mut BufWriter<File>, value: &T) -> io::Result<()> {
+    serde_json::to_writer(&mut *writer, value)?;
+    writer.write_all(b"\n")
+}
+
+//session file read back from disk
+pub struct LoadedSession {
+    pub header: Option<SessionHeader>, //none for files without a header, like plain recordings of the arduino's output
+    pub records: Vec<LogRecord>,
+    pub skipped_lines: usize, //lines that could not be read, for example because the file was damaged
+    pub truncated_bytes: usize, //size of a final record that was cut off in the middle of being written
+}
+
+//reads a session file, recovering it if the last record was cut off by a crash
+//files of plain json lines in the format the arduino sends are accepted too, their records have no host timestamps
+pub fn load_session(path: &Path) -> io::Result<LoadedSession> {
+    let bytes = fs::read(path)
+        .map_err(|e| io::Error::new(e.kind(), format!("Could not read {}: {}", path.display(), e)))?;
+    let mut session = LoadedSession {
+        header: None,
+        records: vec![],
+        skipped_lines: 0,
+        truncated_bytes: 0,
+    };
+    //a complete file always ends with a newline, anything after the last one was still being written
+    let complete = bytes.last() == Some(&b'\n');
+    let lines: Vec<&[u8]> = bytes.split(|&byte| byte == b'\n').collect();
+    let line_count = lines.len();
+    for (index, line) in lines.into_iter().enumerate() {
+        let text = std::str::from_utf8(line).map(str::trim);
+        if text == Ok("") {
+            continue;
+        }
+        match text.ok().and_then(|text| parse_session_line(text, index == 0)) {
+            Some(SessionLine::Header(header)) => session.header = Some(header),
+            Some(SessionLine::Record(record)) => session.records.push(record),
+            //cut off while it was being written, drop it
+            None if index + 1 == line_count && !complete => session.truncated_bytes = line.len(),
+            None => session.skipped_lines += 1,
+        }
+    }
+    Ok(session)
+}
+
+//a line of a session file
+enum SessionLine {
+    Header(SessionHeader),
+    Record(LogRecord),
+}
+
+//parses a single line of a session file, the header is only looked for on the first line
+fn parse_session_line(text: &str, first_line: bool) -> Option<SessionLine> {
+    if let Ok(record) = serde_json::from_str::<LogRecord>(text) {
+        return Some(SessionLine::Record(record));
+    }
+    if let Ok(data) = serde_json::from_str::<ArduinoData>(text) {
+        return Some(SessionLine::Record(LogRecord {
+            host_time_us: 0,
+            wall_time_ms: 0,
+            raw: text.to_string(),
+            data: Some(data),
+            parse_error: None,
+        }));
+    }
+    if first_line
+        && let Ok(header) = serde_json::from_str::<SessionHeader>(text)
+        && header.format == SESSION_FORMAT
+    {
+        return Some(SessionLine::Header(header));
+    }
+    None
+}
+
+//wall clock milliseconds since the unix epoch, zero if the clock is set before 1970
+pub fn wall_time_ms() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |since_epoch| since_epoch.as_millis() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //a file in the temp folder that is deleted again when the test is done with it
+    struct TempFile(PathBuf);
+
+    impl TempFile {
+        fn new(name: &str) -> Self {
+            Self(std::env::temp_dir().join(format!("rocketviewer-{}-{}", std::process::id(), name)))
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    fn record(index: u32) -> LogRecord {
+        let mut extra = serde_json::Map::new();
+        extra.insert("altitude".to_string(), serde_json::Value::from(index as f64 * 1.5));
+        let data = ArduinoData {
+            x: 0.1 * index as f32,
+            y: -0.2,
+            z: 0.3,
+            w: 0.9,
+            time: index * 10,
+            extra,
+        };
+        LogRecord {
+            host_time_us: index as u64 * 10_000,
+            wall_time_ms: 1_700_000_000_000 + index as u64 * 10,
+            raw: serde_json::to_string(&data).unwrap(),
+            data: Some(data),
+            parse_error: None,
+        }
+    }
+
+    //writes a header and the given records through the writer thread's code and returns the file's bytes
+    fn session_bytes(name: &str, records: Vec<LogRecord>) -> Vec<u8> {
+        let file = TempFile::new(name);
+        let header = SessionHeader::new("Test Source".to_string(), Some("COM3".to_string()), Some(115_200));
+        let (sender, receiver) = mpsc::channel();
+        for record in records {
+            sender.send(record).unwrap();
+        }
+        drop(sender);
+        let status = FileStatus::new(file.0.clone(), "Logging", "records");
+        write_session(File::create(&file.0).unwrap(), &header, receiver, &status).unwrap();
+        fs::read(&file.0).unwrap()
+    }
+
+    #[test]
+    fn complete_session_loads_every_record() {
+        let bytes = session_bytes("complete", (0..5).map(record).collect());
+        let file = TempFile::new("complete-copy");
+        fs::write(&file.0, &bytes).unwrap();
+        let session = load_session(&file.0).unwrap();
+        assert_eq!(session.header.unwrap().source, "Test Source");
+        assert_eq!(session.records.len(), 5);
+        assert_eq!(session.records[3].host_time_us, 30_000);
+        assert_eq!(session.skipped_lines, 0);
+        assert_eq!(session.truncated_bytes, 0);
+    }
+
+    #[test]
+    fn truncated_at_every_offset_recovers_complete_records() {
+        let bytes = session_bytes("truncated", (0..4).map(record).collect());
+        let file = TempFile::new("truncated-copy");
+        for cut in 0..=bytes.len() {
+            let kept = &bytes[..cut];
+            fs::write(&file.0, kept).unwrap();
+            let session = load_session(&file.0).unwrap_or_else(|e| panic!("cut at {}: {}", cut, e));
+            //everything after the last newline is the tail that was still being written
+            let complete_end = kept.iter().rposition(|&byte| byte == b'\n').map_or(0, |newline| newline + 1);
+            let complete_lines = kept[..complete_end].iter().filter(|&&byte| byte == b'\n').count();
+            let tail = &kept[complete_end..];
+            if complete_lines == 0 {
+                //cut inside the header, it only counts if it was cut off right before its newline
+                let whole_header = serde_json::from_slice::<SessionHeader>(tail).is_ok();
+                assert_eq!(session.header.is_some(), whole_header, "cut at {}", cut);
+                assert!(session.records.is_empty(), "cut at {}", cut);
+                assert_eq!(session.truncated_bytes, if whole_header { 0 } else { tail.len() }, "cut at {}", cut);
+            } else {
+                //a tail cut off right before its newline is a whole record and is kept
+                let whole_record = serde_json::from_slice::<LogRecord>(tail).is_ok();
+                assert!(session.header.is_some(), "cut at {}", cut);
+                assert_eq!(session.records.len(), complete_lines - 1 + whole_record as usize, "cut at {}", cut);
+                assert_eq!(session.truncated_bytes, if whole_record { 0 } else { tail.len() }, "cut at {}", cut);
+            }
+            assert_eq!(session.skipped_lines, 0, "cut at {}", cut);
+            for (index, record) in session.records.iter().enumerate() {
+                assert_eq!(record.data.as_ref().unwrap().time, index as u32 * 10, "cut at {}", cut);
+            }
+        }
+    }
+
+    #[test]
+    fn damaged_line_in_the_middle_is_skipped() {
+        let mut bytes = session_bytes("damaged", (0..3).map(record).collect());
+        let second_newline = bytes.iter().enumerate().filter(|(_, byte)| **byte == b'\n').nth(1).unwrap().0;
+        bytes.splice(second_newline + 1..second_newline + 1, b"{\"host_time_us\":12,\"wal\n".iter().copied());
+        let file = TempFile::new("damaged-copy");
+        fs::write(&file.0, &bytes).unwrap();
+        let session = load_session(&file.0).unwrap();
+        assert_eq!(session.records.len(), 3);
+        assert_eq!(session.skipped_lines, 1);
+        assert_eq!(session.truncated_bytes, 0);
+    }
+
+    #[test]
+    fn plain_arduino_recording_loads_without_header() {
+        let file = TempFile::new("plain");
+        fs::write(&file.0, b"{\"x\":0,\"y\":0,\"z\":0,\"w\":1,\"time\":5}\n{\"x\":0,\"y\":0,\"z\":0,\"w\":1,\"time\":15}\n").unwrap();
+        let session = load_session(&file.0).unwrap();
+        assert!(session.header.is_none());
+        assert_eq!(session.records.len(), 2);
+        assert_eq!(session.records[1].data.as_ref().unwrap().time, 15);
+    }
+}
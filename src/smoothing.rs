@@ -0,0 +1,140 @@
+//smoothing of the rocket model's orientation between samples, so the model does not jump from one sample to the next
+//snap shows the newest sample as is, exponential slerps toward it, and interpolated shows the orientation from slightly in the past,
+//blended between the two samples around it in the telemetry history
+//everything is scaled by the frame time so the smoothing looks the same at any frame rate
+
+use bevy::prelude::*;
+use bevy_egui::egui;
+use crate::history::TelemetryHistory;
+
+const MAX_CLOCK_ERROR_MS: f64 = 500.0; //if the render clock is this far off it jumps instead of catching up slowly
+const CLOCK_CORRECTION_TIME: f64 = 1.0; //seconds for the render clock to make up most of a small error
+
+//ways the rocket model can follow the samples
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum SmoothingMode {
+    Snap, //show the newest sample right away
+    Exponential, //slerp toward the newest sample, closing most of the gap within the time constant
+    Interpolated, //show the orientation from render_delay_ms ago, interpolated between the samples around that time
+}
+
+impl SmoothingMode {
+    //every mode in the order they are shown in the dropdown
+    pub const ALL: [SmoothingMode; 3] = [
+        SmoothingMode::Snap,
+        SmoothingMode::Exponential,
+        SmoothingMode::Interpolated,
+    ];
+
+    //name shown in the mode dropdown
+    pub fn label(&self) -> &'static str {
+        match self {
+            SmoothingMode::Snap => "Snap",
+            SmoothingMode::Exponential => "Exponential",
+            SmoothingMode::Interpolated => "Interpolated",
+        }
+    }
+}
+
+//resource that holds the smoothing settings and the state needed to smooth between frames
+#[derive(Resource)]
+pub struct OrientationSmoothing {
+    pub mode: SmoothingMode,
+    pub time_constant: f32, //seconds, how quickly exponential smoothing follows the samples
+    pub render_delay_ms: f32, //how far in the past interpolated smoothing shows the rocket, should cover a few samples
+    shown: Quat, //orientation shown last frame
+    render_time_ms: Option<f64>, //timeline position currently shown by interpolated smoothing, none until the first frame
+}
+
+impl Default for OrientationSmoothing {
+    fn default() -> Self {
+        Self {
+            mode: SmoothingMode::Exponential,
+            time_constant: 0.05,
+            render_delay_ms: 50.0, //five samples at the arduino's 100 Hz
+            shown: Quat::IDENTITY,
+            render_time_ms: None,
+        }
+    }
+}
+
+impl OrientationSmoothing {
+    //restarts the render clock, used when a new session or replay starts
+    pub fn reset(&mut self) {
+        self.render_time_ms = None;
+    }
+
+    //orientation to show this frame, target is the newest sample, newest_ms where it is on the history timeline,
+    //and delta_secs the frame time
+    pub fn update(&mut self, target: Quat, newest_ms: f64, history: &TelemetryHistory, delta_secs: f32) -> Quat {
+        let target = target.normalize();
+        self.shown = match self.mode {
+            SmoothingMode::Snap => target,
+            SmoothingMode::Exponential => {
+                //the fraction of the gap closed depends on the frame time, so the speed does not depend on the frame rate
+                let blend = 1.0 - (-delta_secs / self.time_constant.max(f32::EPSILON)).exp();
+                self.shown.slerp(target, blend)
+            }
+            SmoothingMode::Interpolated => self.interpolate(newest_ms, history, delta_secs as f64).unwrap_or(target),
+        };
+        self.shown
+    }
+
+    //advances the render clock by the frame time and interpolates the history at it
+    //the render clock runs at real time and is slowly pulled toward render_delay_ms behind the newest sample,
+    //so uneven sample arrival does not show up as uneven motion
+    //samples after newest_ms are ignored, the replay has the whole recording in the history
+    fn interpolate(&mut self, newest_ms: f64, history: &TelemetryHistory, delta_secs: f64) -> Option<Quat> {
+        let target_time = newest_ms - self.render_delay_ms as f64;
+        let render_time = match self.render_time_ms {
+            Some(render_time) => {
+                let render_time = render_time + delta_secs * 1000.0;
+                let error = target_time - render_time;
+                if error.abs() > MAX_CLOCK_ERROR_MS {
+                    target_time
+                } else {
+                    render_time + error * (1.0 - (-delta_secs / CLOCK_CORRECTION_TIME).exp())
+                }
+            }
+            None => target_time,
+        };
+        let render_time = render_time.min(newest_ms);
+        self.render_time_ms = Some(render_time);
+        let before = history.at_or_before(render_time)?;
+        let after = history.range(render_time, newest_ms).iter().find(|sample| sample.timeline_ms > render_time);
+        let quat = match after {
+            Some(after) if before.timeline_ms <= render_time => {
+                let blend = (render_time - before.timeline_ms) / (after.timeline_ms - before.timeline_ms);
+                before.quat.slerp(after.quat, blend as f32)
+            }
+            //before the first sample or at the newest one, hold the closest sample
+            _ => before.quat,
+        };
+        Some(quat)
+    }
+}
+
+//smoothing settings, shown in the monitor window
+pub fn show_smoothing_settings(ui: &mut egui::Ui, smoothing: &mut OrientationSmoothing) {
+    ui.horizontal(|ui| {
+        ui.label("Smoothing:");
+        egui::ComboBox::from_id_salt("smoothing_mode")
+            .selected_text(smoothing.mode.label())
+            .show_ui(ui, |ui| {
+                for mode in SmoothingMode::ALL {
+                    ui.selectable_value(&mut smoothing.mode, mode, mode.label());
+                }
+            });
+        match smoothing.mode {
+            SmoothingMode::Snap => (),
+            SmoothingMode::Exponential => {
+                ui.label("Time Constant:");
+                ui.add(egui::DragValue::new(&mut smoothing.time_constant).range(0.001..=2.0).speed(0.005).suffix(" s"));
+            }
+            SmoothingMode::Interpolated => {
+                ui.label("Delay:");
+                ui.add(egui::DragValue::new(&mut smoothing.render_delay_ms).range(0.0..=1_000.0).suffix(" ms"));
+            }
+        }
+    });
+}
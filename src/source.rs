@@ -0,0 +1,50 @@
+//telemetry sources the reader thread can read from
+//every transport implements the TelemetrySource trait so the reader thread, the framer, and the ui do not care where the data comes from
+
+use std::io;
+
+mod raw_replay;
+mod serial;
+mod simulator;
+mod tcp;
+mod udp;
+pub use raw_replay::RawReplaySource;
+pub use serial::SerialSource;
+pub use simulator::{MotionProfile, SimulatorSettings, SimulatorSource};
+pub use tcp::TcpSource;
+pub use udp::UdpSource;
+
+//what a telemetry source hands back from a single poll
+pub enum SourceRead {
+    Bytes(Vec<u8>), //raw bytes from a byte stream, these still have to go through the line framer
+    Records(Vec<String>), //records that are already split up by the transport, one json object each
+}
+
+//anything the viewer can read telemetry from
+//sources are polled over and over from the reader thread, a poll may block for a short time but should not block forever
+//so the reader thread can notice when it is told to stop
+pub trait TelemetrySource: Send {
+    //short description of the source shown in the ui, for example the port name and baud rate
+    fn describe(&self) -> String;
+
+    //reads whatever data arrived since the last poll, an empty read means nothing arrived before the source timed out
+    //an error means the source is broken until reconnect succeeds
+    fn poll(&mut self) -> io::Result<SourceRead>;
+
+    //extra lines of status shown in the ui under the description, for example packet counts
+    fn details(&self) -> Vec<String> {
+        vec![]
+    }
+
+    //false if the source still has to be connected by reconnect before it can be polled
+    //the reader thread checks this once when it starts, after that it goes by the errors poll returns
+    fn is_connected(&self) -> bool {
+        true
+    }
+
+    //called by the reader thread after poll returned an error, tries to get the source working again
+    //the reader thread keeps calling this until it succeeds, sources that can not reconnect return an Unsupported error
+    fn reconnect(&mut self) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::Unsupported, "this source can not reconnect"))
+    }
+}
@@ -0,0 +1,87 @@
+//raw capture replay source, feeds the chunks of a raw capture file back to the reader thread
+//every chunk is handed over on its own at the same time after the start as it was originally read,
+//so the framer sees exactly the same reads it did live and parsing bugs can be reproduced
+//disconnects in the capture are played back as a failed poll followed by a reconnect, so the reader thread drops the same unfinished lines
+
+use std::io;
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+use super::{SourceRead, TelemetrySource};
+use crate::raw_capture::{RawEntry, load_raw_capture};
+
+const MAX_POLL_SLEEP: Duration = Duration::from_millis(50); //longest a poll sleeps waiting for the next chunk
+
+//telemetry source that plays back a raw capture file
+pub struct RawReplaySource {
+    path: String,
+    entries: Vec<RawEntry>,
+    truncated_bytes: usize, //size of the cut off last entry that was dropped when the file was loaded
+    next_entry: usize, //index of the next entry to hand over
+    start: Instant, //when playback started, chunk times are measured from here
+    damaged: bool, //set once an entry turned out to be garbage, playback can not go on past it
+}
+
+impl RawReplaySource {
+    //loads the capture file, playback starts right away
+    pub fn open(path: &str) -> io::Result<Self> {
+        let capture = load_raw_capture(Path::new(path))?;
+        if !capture.entries.iter().any(|entry| matches!(entry, RawEntry::Chunk { .. })) {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("{} does not contain any captured bytes", path)));
+        }
+        Ok(Self {
+            path: path.to_string(),
+            entries: capture.entries,
+            truncated_bytes: capture.truncated_bytes,
+            next_entry: 0,
+            start: Instant::now(),
+            damaged: false,
+        })
+    }
+}
+
+impl TelemetrySource for RawReplaySource {
+    fn describe(&self) -> String {
+        format!("Raw capture {}", self.path)
+    }
+
+    fn poll(&mut self) -> io::Result<SourceRead> {
+        //once every entry has been played the source just stays quiet, like a board that stopped sending
+        let Some(entry) = self.entries.get(self.next_entry) else {
+            thread::sleep(MAX_POLL_SLEEP);
+            return Ok(SourceRead::Bytes(vec![]));
+        };
+        //a damaged length field throws off every header after it, so the time can be anything
+        let Some(due) = self.start.checked_add(Duration::from_micros(entry.host_time_us())) else {
+            self.damaged = true;
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("Entry {} has a receive time that is out of range, the capture is damaged", self.next_entry + 1)));
+        };
+        let now = Instant::now();
+        if now < due {
+            thread::sleep((due - now).min(MAX_POLL_SLEEP));
+            return Ok(SourceRead::Bytes(vec![]));
+        }
+        //one chunk per poll so the chunk boundaries stay the same as when they were captured
+        self.next_entry += 1;
+        match entry {
+            RawEntry::Chunk { bytes, .. } => Ok(SourceRead::Bytes(bytes.clone())),
+            RawEntry::Disconnect { .. } => Err(io::Error::new(io::ErrorKind::ConnectionReset, "The source disconnected here in the capture")),
+        }
+    }
+
+    //the capture just carries on after a disconnect, so reconnecting always works right away unless the capture is damaged
+    fn reconnect(&mut self) -> io::Result<()> {
+        if self.damaged {
+            return Err(io::Error::new(io::ErrorKind::Unsupported, "The capture is damaged"));
+        }
+        Ok(())
+    }
+
+    fn details(&self) -> Vec<String> {
+        let mut details = vec![format!("Entry {} of {}", self.next_entry, self.entries.len())];
+        if self.truncated_bytes > 0 {
+            details.push(format!("Dropped {} bytes of an entry that was cut off", self.truncated_bytes));
+        }
+        details
+    }
+}
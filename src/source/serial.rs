@@ -0,0 +1,109 @@
+//serial port telemetry source, reads the newline separated json the arduino prints
+
+use std::io::{self, Read};
+use std::time::Duration;
+use serialport5::{self, SerialPortBuilder, SerialPort, SerialPortType};
+use super::{SourceRead, TelemetrySource};
+
+const READ_BUFFER_SIZE: usize = 1024; //size of the buffer filled on each read from the serial port
+const READ_TIMEOUT: Duration = Duration::from_millis(100); //how long a poll waits for data before giving up, so a quiet port can still be stopped
+
+//telemetry source that reads from a serial port
+//if the port goes away it is closed, and reconnect reopens it with the same baud rate once it shows up again
+pub struct SerialSource {
+    port: Option<SerialPort>, //none while the port is gone
+    port_name: String, //name the port is currently open under, can change after a reconnect
+    baud_rate: u32,
+    target: ReconnectTarget,
+}
+
+impl SerialSource {
+    //opens the named serial port at the given baud rate
+    pub fn open(port_name: &str, baud_rate: u32) -> io::Result<Self> {
+        let port = open_port(port_name, baud_rate)
+            .map_err(|e| io::Error::other(format!("Could not open {} at {} baud: {}", port_name, baud_rate, e)))?;
+        Ok(Self {
+            port: Some(port),
+            port_name: port_name.to_string(),
+            baud_rate,
+            target: ReconnectTarget::new(port_name),
+        })
+    }
+}
+
+impl TelemetrySource for SerialSource {
+    fn describe(&self) -> String {
+        format!("{} at {} baud", self.port_name, self.baud_rate)
+    }
+
+    fn poll(&mut self) -> io::Result<SourceRead> {
+        let Some(port) = self.port.as_mut() else {
+            return Err(io::Error::new(io::ErrorKind::NotConnected, format!("{} is not open", self.port_name)));
+        };
+        let mut buffer = [0; READ_BUFFER_SIZE];
+        match port.read(&mut buffer) {
+            Ok(bytes_read) => Ok(SourceRead::Bytes(buffer[..bytes_read].to_vec())),
+            Err(ref e) if e.kind() == io::ErrorKind::TimedOut => Ok(SourceRead::Bytes(vec![])),
+            Err(e) => {
+                //close the port so it can be reopened once it comes back
+                self.port = None;
+                Err(io::Error::new(e.kind(), format!("Error reading from serial port: {}", e)))
+            }
+        }
+    }
+
+    fn reconnect(&mut self) -> io::Result<()> {
+        let port_name = self.target.find().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("{} has not come back yet", self.target.port_name)))?;
+        let port = open_port(&port_name, self.baud_rate)
+            .map_err(|e| io::Error::other(format!("Could not reopen {}: {}", port_name, e)))?;
+        self.port = Some(port);
+        self.port_name = port_name;
+        Ok(())
+    }
+}
+
+//opens a serial port with the read timeout set, so reads never block the reader thread for long
+fn open_port(port_name: &str, baud_rate: u32) -> serialport5::Result<SerialPort> {
+    SerialPortBuilder::new()
+        .baud_rate(baud_rate)
+        .read_timeout(Some(READ_TIMEOUT))
+        .open(port_name)
+}
+
+//what the serial source looks for when the port it was reading from goes away
+//usb serial adapters can come back under a different name after being replugged, so the usb serial number is checked too
+struct ReconnectTarget {
+    port_name: String,
+    serial_number: Option<String>,
+}
+
+impl ReconnectTarget {
+    //builds a target for the given port, looking up its usb serial number if it has one
+    fn new(port_name: &str) -> Self {
+        let serial_number = serialport5::available_ports()
+            .unwrap_or_default()
+            .into_iter()
+            .find(|port| port.port_name == port_name)
+            .and_then(|port| match port.port_type {
+                SerialPortType::UsbPort(usb_info) => usb_info.serial_number,
+                _ => None,
+            });
+        Self {
+            port_name: port_name.to_string(),
+            serial_number,
+        }
+    }
+
+    //looks through the available ports for the one we lost, returns its current name if it is back
+    //a port with the same name wins over one with the same usb serial number
+    fn find(&self) -> Option<String> {
+        let ports = serialport5::available_ports().ok()?;
+        if ports.iter().any(|port| port.port_name == self.port_name) {
+            return Some(self.port_name.clone());
+        }
+        let serial_number = self.serial_number.as_ref()?;
+        ports.into_iter()
+            .find(|port| matches!(&port.port_type, SerialPortType::UsbPort(usb_info) if usb_info.serial_number.as_ref() == Some(serial_number)))
+            .map(|port| port.port_name)
+    }
+}
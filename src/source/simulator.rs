@@ -0,0 +1,225 @@
+//synthetic telemetry source, generates the same json the arduino sends so the viewer can be worked on without a board
+//samples are produced on a fixed clock from one of a few motion profiles, with optional noise on top
+
+use std::f32::consts::TAU;
+use std::io;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use bevy::math::{Quat, Vec3};
+use super::{SourceRead, TelemetrySource};
+use crate::ArduinoData;
+
+const MAX_POLL_SLEEP: Duration = Duration::from_millis(50); //longest a poll sleeps waiting for the next sample
+const MAX_SAMPLES_PER_POLL: usize = 1_000; //if the simulator falls further behind than this it skips ahead instead of catching up
+const LAUNCH_SCRIPT_LENGTH: f32 = 20.0; //seconds before the launch and tumble script starts over
+
+//motions the simulator can generate
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum MotionProfile {
+    Spin, //constant spin about the spin axis
+    Coning, //the rocket's long axis sweeps around a cone about the vertical
+    LaunchAndTumble, //scripted flight: pad, boost, coast, tumble at apogee, then swinging under the chute
+}
+
+impl MotionProfile {
+    //every profile in the order they are shown in the dropdown
+    pub const ALL: [MotionProfile; 3] = [
+        MotionProfile::Spin,
+        MotionProfile::Coning,
+        MotionProfile::LaunchAndTumble,
+    ];
+
+    //name shown in the profile dropdown
+    pub fn label(&self) -> &'static str {
+        match self {
+            MotionProfile::Spin => "Constant Spin",
+            MotionProfile::Coning => "Coning",
+            MotionProfile::LaunchAndTumble => "Launch and Tumble",
+        }
+    }
+}
+
+//settings for the simulator, edited in the source options before the simulator is started
+#[derive(Debug, Clone)]
+pub struct SimulatorSettings {
+    pub profile: MotionProfile,
+    pub rate_hz: f32, //samples per second
+    pub spin_axis: Vec3, //axis for the constant spin profile, does not have to be normalized
+    pub spin_rate: f32, //degrees per second, used by the spin profile and as the coning rate
+    pub cone_half_angle: f32, //degrees between the rocket's long axis and the vertical for the coning profile
+    pub noise: f32, //standard deviation of the noise added to each quaternion component, 0 for none
+}
+
+impl Default for SimulatorSettings {
+    fn default() -> Self {
+        Self {
+            profile: MotionProfile::Spin,
+            rate_hz: 100.0, //same rate as SendSerialData.ino
+            spin_axis: Vec3::Y,
+            spin_rate: 100.0,
+            cone_half_angle: 15.0,
+            noise: 0.0,
+        }
+    }
+}
+
+//telemetry source that generates samples from a motion profile instead of reading them from a device
+pub struct SimulatorSource {
+    settings: SimulatorSettings,
+    start: Instant,
+    next_sample: Instant, //when the next sample is due
+    interval: Duration, //time between samples
+    rng: XorShift,
+}
+
+impl SimulatorSource {
+    //creates a simulator with the given settings, the simulated device clock starts at zero
+    pub fn new(settings: SimulatorSettings) -> io::Result<Self> {
+        if !settings.rate_hz.is_finite() || settings.rate_hz <= 0.0 {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "Simulator rate has to be above 0 Hz"));
+        }
+        let start = Instant::now();
+        let seed = SystemTime::now().duration_since(UNIX_EPOCH).map_or(1, |since_epoch| since_epoch.as_nanos() as u64);
+        Ok(Self {
+            interval: Duration::from_secs_f32(1.0 / settings.rate_hz),
+            settings,
+            start,
+            next_sample: start,
+            rng: XorShift::new(seed),
+        })
+    }
+
+    //builds the sample for the given simulated time as the json line the arduino would send
+    fn sample_at(&mut self, elapsed: Duration) -> io::Result<String> {
+        let mut quat = orientation_at(&self.settings, elapsed.as_secs_f32());
+        if self.settings.noise > 0.0 {
+            let noise = Vec3::new(self.rng.gaussian(), self.rng.gaussian(), self.rng.gaussian()) * self.settings.noise;
+            let noisy = Quat::from_xyzw(quat.x + noise.x, quat.y + noise.y, quat.z + noise.z, quat.w + self.rng.gaussian() * self.settings.noise);
+            quat = noisy.normalize();
+        }
+        let data = ArduinoData {
+            x: quat.x,
+            y: quat.y,
+            z: quat.z,
+            w: quat.w,
+            time: elapsed.as_millis() as u32, //wraps like millis() does on the arduino
+            extra: serde_json::Map::new(),
+        };
+        serde_json::to_string(&data).map_err(io::Error::other)
+    }
+}
+
+impl TelemetrySource for SimulatorSource {
+    fn describe(&self) -> String {
+        format!("Simulator ({}, {} Hz)", self.settings.profile.label(), self.settings.rate_hz)
+    }
+
+    fn poll(&mut self) -> io::Result<SourceRead> {
+        let now = Instant::now();
+        if now < self.next_sample {
+            thread::sleep((self.next_sample - now).min(MAX_POLL_SLEEP));
+            return Ok(SourceRead::Records(vec![]));
+        }
+        //produce every sample that has come due since the last poll
+        let mut records = vec![];
+        while self.next_sample <= Instant::now() && records.len() < MAX_SAMPLES_PER_POLL {
+            let elapsed = self.next_sample - self.start;
+            records.push(self.sample_at(elapsed)?);
+            self.next_sample += self.interval;
+        }
+        if self.next_sample < Instant::now() {
+            self.next_sample = Instant::now();
+        }
+        Ok(SourceRead::Records(records))
+    }
+
+    fn details(&self) -> Vec<String> {
+        vec![format!("Simulated time: {:.1}s", self.start.elapsed().as_secs_f32())]
+    }
+}
+
+//orientation of the simulated rocket at the given number of seconds since the simulator started
+//the rocket's long axis is y, same as the model
+fn orientation_at(settings: &SimulatorSettings, seconds: f32) -> Quat {
+    match settings.profile {
+        MotionProfile::Spin => {
+            let axis = settings.spin_axis.try_normalize().unwrap_or(Vec3::Y);
+            Quat::from_axis_angle(axis, (settings.spin_rate * seconds).to_radians())
+        }
+        MotionProfile::Coning => {
+            //tilt away from the vertical, then sweep the tilt around the vertical
+            let sweep = (settings.spin_rate * seconds).to_radians();
+            Quat::from_rotation_y(sweep) * Quat::from_rotation_x(settings.cone_half_angle.to_radians())
+        }
+        MotionProfile::LaunchAndTumble => launch_and_tumble(seconds % LAUNCH_SCRIPT_LENGTH),
+    }
+}
+
+//scripted flight for the launch and tumble profile, seconds is the time since the script started
+fn launch_and_tumble(seconds: f32) -> Quat {
+    //roll rate during boost and coast, the rocket spins up off the rail
+    const ROLL_RATE: f32 = 180.0;
+    //end of each phase in seconds
+    const PAD_END: f32 = 3.0;
+    const BOOST_END: f32 = 6.0;
+    const COAST_END: f32 = 10.0;
+    const TUMBLE_END: f32 = 14.0;
+
+    //pitch over from vertical and roll about the long axis, shared by boost and coast
+    let flight = |seconds: f32, pitch: f32| {
+        Quat::from_rotation_x(pitch.to_radians()) * Quat::from_rotation_y((ROLL_RATE * (seconds - PAD_END)).to_radians())
+    };
+    //pitch over a little during boost, then more as the rocket slows down during coast
+    let pitch_at = |seconds: f32| {
+        if seconds < BOOST_END {
+            5.0 * (seconds - PAD_END) / (BOOST_END - PAD_END)
+        } else {
+            5.0 + 25.0 * (seconds - BOOST_END) / (COAST_END - BOOST_END)
+        }
+    };
+
+    if seconds < PAD_END {
+        //sitting upright on the pad
+        Quat::IDENTITY
+    } else if seconds < COAST_END {
+        flight(seconds, pitch_at(seconds))
+    } else if seconds < TUMBLE_END {
+        //tumble end over end at apogee, starting from where coast left off
+        let apogee = flight(COAST_END, pitch_at(COAST_END));
+        let tumble_axis = Vec3::new(1.0, 0.3, 0.5).normalize();
+        Quat::from_axis_angle(tumble_axis, (400.0 * (seconds - COAST_END)).to_radians()) * apogee
+    } else {
+        //hanging nose down under the chute, swinging and slowly turning
+        let descent = seconds - TUMBLE_END;
+        let swing = 15.0 * (TAU * descent / 2.0).sin();
+        Quat::from_rotation_y((30.0 * descent).to_radians()) * Quat::from_rotation_x((180.0 + swing).to_radians())
+    }
+}
+
+//small xorshift random number generator for the noise, good enough for fake sensor data
+struct XorShift {
+    state: u64,
+}
+
+impl XorShift {
+    //seeds the generator, a zero seed would get stuck at zero so it is replaced
+    fn new(seed: u64) -> Self {
+        Self {
+            state: if seed == 0 { 0x2545_F491_4F6C_DD1D } else { seed },
+        }
+    }
+
+    //uniform random number in (0, 1]
+    fn uniform(&mut self) -> f32 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        ((self.state >> 40) as f32 + 1.0) / (1u64 << 24) as f32
+    }
+
+    //normally distributed random number with a standard deviation of 1, using the box-muller transform
+    fn gaussian(&mut self) -> f32 {
+        let (u1, u2) = (self.uniform(), self.uniform());
+        (-2.0 * u1.ln()).sqrt() * (TAU * u2).cos()
+    }
+}
@@ -0,0 +1,114 @@
+//tcp client telemetry source, for ser2net or other bridges that put the serial stream on a tcp socket
+//the stream carries the same newline separated json as the serial port so it goes through the line framer too
+
+use std::io::{self, Read};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::{Duration, Instant};
+use super::{SourceRead, TelemetrySource};
+
+const READ_BUFFER_SIZE: usize = 1024; //size of the buffer filled on each read from the socket
+const READ_TIMEOUT: Duration = Duration::from_millis(100); //how long a poll waits for data before giving up
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(3); //how long a single connection attempt may take
+const MIN_BACKOFF: Duration = Duration::from_millis(500); //wait before the first reconnect attempt
+const MAX_BACKOFF: Duration = Duration::from_secs(10); //longest wait between reconnect attempts
+
+//telemetry source that connects to a tcp server and reads the byte stream from it
+//if the connection drops, reconnect tries again with an exponential backoff so a dead bridge is not hammered
+pub struct TcpSource {
+    address: String, //host:port as typed by the user
+    stream: Option<TcpStream>, //none while disconnected
+    failed_attempts: u32, //reconnect attempts that failed since the connection dropped
+    next_attempt: Instant, //reconnect does nothing until this time has passed
+}
+
+impl TcpSource {
+    //sets up a source for the given host:port without connecting yet
+    //the reader thread makes the first connection through reconnect, so a bridge that is not up yet backs off like a dropped one
+    pub fn new(address: &str) -> Self {
+        Self {
+            address: address.to_string(),
+            stream: None,
+            failed_attempts: 0,
+            next_attempt: Instant::now(),
+        }
+    }
+
+    //how long to wait after the given number of failed attempts, doubles each time up to the max
+    fn backoff(failed_attempts: u32) -> Duration {
+        MIN_BACKOFF.saturating_mul(2_u32.saturating_pow(failed_attempts)).min(MAX_BACKOFF)
+    }
+}
+
+impl TelemetrySource for TcpSource {
+    fn describe(&self) -> String {
+        format!("TCP {}", self.address)
+    }
+
+    fn poll(&mut self) -> io::Result<SourceRead> {
+        let Some(stream) = self.stream.as_mut() else {
+            return Err(io::Error::new(io::ErrorKind::NotConnected, format!("Not connected to {}", self.address)));
+        };
+        let mut buffer = [0; READ_BUFFER_SIZE];
+        let result = match stream.read(&mut buffer) {
+            //a read of zero bytes means the other end closed the connection
+            Ok(0) => Err(io::Error::new(io::ErrorKind::UnexpectedEof, format!("{} closed the connection", self.address))),
+            Ok(bytes_read) => return Ok(SourceRead::Bytes(buffer[..bytes_read].to_vec())),
+            //timeouts show up as WouldBlock on unix and TimedOut on windows
+            Err(ref e) if matches!(e.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut) => return Ok(SourceRead::Bytes(vec![])),
+            Err(e) => Err(io::Error::new(e.kind(), format!("Error reading from {}: {}", self.address, e))),
+        };
+        //drop the connection and start the backoff from the beginning
+        self.stream = None;
+        self.failed_attempts = 0;
+        self.next_attempt = Instant::now() + Self::backoff(0);
+        result
+    }
+
+    fn reconnect(&mut self) -> io::Result<()> {
+        if Instant::now() < self.next_attempt {
+            return Err(io::Error::new(io::ErrorKind::WouldBlock, "Waiting before the next reconnect attempt"));
+        }
+        match open_stream(&self.address) {
+            Ok(stream) => {
+                self.stream = Some(stream);
+                self.failed_attempts = 0;
+                Ok(())
+            }
+            Err(e) => {
+                self.failed_attempts += 1;
+                self.next_attempt = Instant::now() + Self::backoff(self.failed_attempts);
+                Err(e)
+            }
+        }
+    }
+
+    fn is_connected(&self) -> bool {
+        self.stream.is_some()
+    }
+
+    fn details(&self) -> Vec<String> {
+        if self.stream.is_some() {
+            return vec![];
+        }
+        let wait = self.next_attempt.saturating_duration_since(Instant::now());
+        vec![
+            format!("Failed connection attempts: {}", self.failed_attempts),
+            format!("Next attempt in {:.1}s", wait.as_secs_f32()),
+        ]
+    }
+}
+
+//resolves the address and connects to the first address that accepts the connection
+fn open_stream(address: &str) -> io::Result<TcpStream> {
+    let mut last_error = io::Error::new(io::ErrorKind::InvalidInput, format!("{} did not resolve to any address", address));
+    for socket_address in address.to_socket_addrs()? {
+        match TcpStream::connect_timeout(&socket_address, CONNECT_TIMEOUT) {
+            Ok(stream) => {
+                stream.set_read_timeout(Some(READ_TIMEOUT))?;
+                return Ok(stream);
+            }
+            Err(e) => last_error = io::Error::new(e.kind(), format!("Could not connect to {}: {}", address, e)),
+        }
+    }
+    Err(last_error)
+}
@@ -0,0 +1,91 @@
+//udp telemetry source, for radio ground modems that forward each packet as a datagram
+//every datagram holds exactly one json record so there is no framing to do
+
+use std::io;
+use std::net::{SocketAddr, UdpSocket};
+use std::time::{Duration, Instant};
+use super::{SourceRead, TelemetrySource};
+
+const MAX_DATAGRAM_SIZE: usize = 65_507; //largest payload a udp datagram can carry
+const RECEIVE_TIMEOUT: Duration = Duration::from_millis(100); //how long a poll waits for a datagram before giving up
+const RATE_WINDOW: Duration = Duration::from_secs(1); //how often the packet rate is recalculated
+
+//telemetry source that listens for datagrams on a udp socket
+//keeps track of who sent the last datagram and how many datagrams are arriving per second
+pub struct UdpSource {
+    socket: UdpSocket,
+    local_address: SocketAddr,
+    buffer: Vec<u8>, //datagrams are received into this, kept around so a poll does not allocate it every time
+    last_sender: Option<SocketAddr>,
+    packets: u64, //total datagrams received
+    packet_rate: f32, //datagrams per second over the last rate window
+    window_start: Instant,
+    window_packets: u64, //datagrams received since window_start
+}
+
+impl UdpSource {
+    //binds a udp socket to the given address and port
+    pub fn bind(bind_address: &str, port: u16) -> io::Result<Self> {
+        let socket = UdpSocket::bind((bind_address, port))
+            .map_err(|e| io::Error::new(e.kind(), format!("Could not bind udp socket to {}:{}: {}", bind_address, port, e)))?;
+        socket.set_read_timeout(Some(RECEIVE_TIMEOUT))?;
+        let local_address = socket.local_addr()?;
+        Ok(Self {
+            socket,
+            local_address,
+            buffer: vec![0; MAX_DATAGRAM_SIZE],
+            last_sender: None,
+            packets: 0,
+            packet_rate: 0.0,
+            window_start: Instant::now(),
+            window_packets: 0,
+        })
+    }
+
+    //recalculates the packet rate once the current rate window is over
+    fn update_rate(&mut self) {
+        let elapsed = self.window_start.elapsed();
+        if elapsed >= RATE_WINDOW {
+            self.packet_rate = self.window_packets as f32 / elapsed.as_secs_f32();
+            self.window_start = Instant::now();
+            self.window_packets = 0;
+        }
+    }
+}
+
+impl TelemetrySource for UdpSource {
+    fn describe(&self) -> String {
+        format!("UDP on {}", self.local_address)
+    }
+
+    fn poll(&mut self) -> io::Result<SourceRead> {
+        let received = self.socket.recv_from(&mut self.buffer);
+        let records = match received {
+            Ok((bytes_read, sender)) => {
+                self.last_sender = Some(sender);
+                self.packets += 1;
+                self.window_packets += 1;
+                vec![String::from_utf8_lossy(&self.buffer[..bytes_read]).into_owned()]
+            }
+            //timeouts show up as WouldBlock on unix and TimedOut on windows
+            Err(ref e) if matches!(e.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut) => vec![],
+            //a previous datagram bounced off a closed port on windows, nothing is wrong with the socket itself
+            Err(ref e) if e.kind() == io::ErrorKind::ConnectionReset => vec![],
+            Err(e) => return Err(e),
+        };
+        self.update_rate();
+        Ok(SourceRead::Records(records))
+    }
+
+    fn details(&self) -> Vec<String> {
+        let sender = match self.last_sender {
+            Some(sender) => sender.to_string(),
+            None => "nobody yet".to_string(),
+        };
+        vec![
+            format!("Last sender: {}", sender),
+            format!("Packets: {}", self.packets),
+            format!("Packet rate: {:.1}/s", self.packet_rate),
+        ]
+    }
+}